@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
 use std::io::{BufWriter, Write};
@@ -7,30 +8,65 @@ fn main() {
     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen.rs");
     let mut file = BufWriter::new(File::create(path).unwrap());
 
+    // Codes, names, numeric codes and minor units come from ISO 4217.
+    // Symbols and separators come from currency.json, keyed by code.
+    let iso = include_str!("config/iso4217.json");
+    let iso = serde_json::from_str::<serde_json::Value>(iso).expect("invalid iso4217.json");
     let currencies = include_str!("config/currency.json");
     let value = serde_json::from_str::<serde_json::Value>(currencies).expect("invalid currency.json");
 
-    let mut map = phf_codegen::Map::new();
+    let mut formats = HashMap::new();
     if let Some(arr) = value["currency_types"].as_array() {
         for val in arr {
-            map.entry(val["value"].as_str().unwrap(), &format!("CurrencyConfig{{
+            formats.insert(val["value"].as_str().unwrap(), val);
+        }
+    }
+
+    let mut map = phf_codegen::Map::new();
+    for val in iso["currencies"].as_array().expect("invalid iso4217.json") {
+        let code = val["code"].as_str().unwrap();
+        let format = formats.remove(code);
+        let field = |name: &str, default: &'static str| {
+            format.and_then(|x| x[name].as_str()).unwrap_or(default).to_owned()
+        };
+        // Currencies without a format in currency.json show their code after the amount
+        let symbol = format.and_then(|x| x["symbol"].as_str()).unwrap_or(code);
+        // Digit group sizes, 3 unless currency.json says otherwise
+        let grouping = format
+            .and_then(|x| x["grouping"].as_array())
+            .map(|x| x.iter().map(|size| size.as_u64().unwrap().to_string()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_else(|| "3".to_owned());
+        // Gold, SDR and the like have no minor unit
+        let precision = match val["minor_units"].as_i64() {
+            Some(precision) => format!("Some({})", precision),
+            None => "None".to_owned(),
+        };
+
+        map.entry(code, &format!("CurrencyConfig{{
                 value: \"{}\",
                 name: \"{}\",
+                numeric_code: \"{}\",
                 symbol: \"{}\",
                 is_symbol_prefix: {},
                 separator: \"{}\",
                 delimiter: \"{}\",
-                precision: Some({}),
+                grouping: &[{}],
+                precision: {},
             }}",
-                val["value"].as_str().unwrap(),
-                val["delimiter"].as_str().unwrap(),
-                val["symbol"].as_str().unwrap(),
-                val["is_symbol_prefix"].as_bool().unwrap(),
-                val["separator"].as_str().unwrap(),
-                val["delimiter"].as_str().unwrap(),
-                val["precision"].as_i64().unwrap_or(2),
-            ));
-        }
+            code,
+            val["name"].as_str().unwrap(),
+            val["numeric"].as_str().unwrap(),
+            symbol,
+            format.and_then(|x| x["is_symbol_prefix"].as_bool()).unwrap_or(false),
+            field("separator", ","),
+            field("delimiter", "."),
+            grouping,
+            precision,
+        ));
+    }
+
+    if let Some(code) = formats.keys().next() {
+        panic!("{} is in currency.json but not in iso4217.json", code);
     }
 
     write!(
@@ -39,4 +75,4 @@ fn main() {
     )
     .unwrap();
     writeln!(&mut file, ";").unwrap();
-}
\ No newline at end of file
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+
+use base64::prelude::*;
+use lol_html::{element, rewrite_str, RewriteStrSettings};
+use ring::rand::SecureRandom;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::error::Error;
+use crate::utils::html_attribute_decode;
+use crate::filters::aes;
+
+const NONCE_LEN: usize = 12;
+
+/// A link rewritten for click tracking. This is what a tracking payload decodes to.
+/// Field names are kept to one letter, as the payload ends up in the url.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedLink {
+    #[serde(rename = "u")]
+    pub url: String,
+    /// Position of the link among the tracked links of the render, starting at 0.
+    #[serde(rename = "i")]
+    pub index: usize,
+    #[serde(rename = "r")]
+    pub recipient_id: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Protection {
+    // base64url(json).base64url(hmac-sha256)
+    Signed,
+    // base64url(nonce || chacha20-poly1305 ciphertext and tag)
+    Encrypted,
+}
+
+/// Rewrites every http link of the rendered html to {base_url}{payload}.
+/// The payload carries the original url, the link index and the recipient id,
+/// and is either signed with hmac-sha256 or encrypted, so the tracking service
+/// can trust it. verify decodes and validates a payload.
+/// mailto:, tel: and other non http links, relative links and links with a data-no-track
+/// attribute are not touched.
+#[derive(Clone)]
+pub struct ClickTracking {
+    base_url: String,
+    recipient_id: String,
+    key: Vec<u8>,
+    protection: Protection,
+}
+
+impl ClickTracking {
+    /// Payloads are readable, but can't be altered without the key.
+    pub fn signed(base_url: String, key: Vec<u8>) -> Self {
+        Self {
+            base_url,
+            recipient_id: String::new(),
+            key,
+            protection: Protection::Signed,
+        }
+    }
+
+    /// Payloads are encrypted and authenticated with chacha20-poly1305. The key must be 32 bytes.
+    pub fn encrypted(base_url: String, key: Vec<u8>) -> Result<Self, Error> {
+        if key.len() != 32 {
+            return Err(Error::InternalError(format!(
+                "Expected a 32 byte key, got {} bytes",
+                key.len()
+            )));
+        }
+        Ok(Self {
+            base_url,
+            recipient_id: String::new(),
+            key,
+            protection: Protection::Encrypted,
+        })
+    }
+
+    pub fn set_recipient_id(&mut self, recipient_id: String) {
+        self.recipient_id = recipient_id;
+    }
+
+    pub fn payload(&self, link: &TrackedLink) -> Result<String, Error> {
+        let json = serde_json::to_string(link).map_err(|err| Error::InternalError(err.to_string()))?;
+
+        match self.protection {
+            Protection::Signed => {
+                let message = BASE64_URL_SAFE_NO_PAD.encode(json);
+                let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, &self.key);
+                let tag = ring::hmac::sign(&key, message.as_bytes());
+                Ok(format!("{}.{}", message, BASE64_URL_SAFE_NO_PAD.encode(tag.as_ref())))
+            }
+            Protection::Encrypted => {
+                let mut nonce = [0u8; NONCE_LEN];
+                ring::rand::SystemRandom::new()
+                    .fill(&mut nonce)
+                    .map_err(|_err| Error::InternalError("Random nonce generation failed".to_owned()))?;
+                let encrypted = aes::encrypt(
+                    &json,
+                    &hex::encode(&self.key),
+                    Some(&hex::encode(nonce)),
+                    Some("chacha20-poly1305"),
+                    false,
+                )
+                .map_err(|err| Error::InternalError(err.to_string()))?;
+
+                let mut bytes = nonce.to_vec();
+                // encrypt returns hex of its own making
+                bytes.extend(hex::decode(encrypted).unwrap());
+                Ok(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+            }
+        }
+    }
+
+    /// Decodes a payload made by this configuration, failing when it was altered or made with another key.
+    pub fn verify(&self, payload: &str) -> Result<TrackedLink, Error> {
+        let invalid = |cause: &str| Error::InvalidTrackingPayload(cause.to_owned());
+
+        let json = match self.protection {
+            Protection::Signed => {
+                let (message, signature) = payload.split_once('.').ok_or_else(|| invalid("missing signature"))?;
+                let signature = BASE64_URL_SAFE_NO_PAD
+                    .decode(signature)
+                    .map_err(|_err| invalid("signature is not base64url"))?;
+                let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, &self.key);
+                ring::hmac::verify(&key, message.as_bytes(), &signature)
+                    .map_err(|_err| invalid("signature mismatch"))?;
+
+                let json = BASE64_URL_SAFE_NO_PAD
+                    .decode(message)
+                    .map_err(|_err| invalid("payload is not base64url"))?;
+                String::from_utf8(json).map_err(|_err| invalid("payload is not utf8"))?
+            }
+            Protection::Encrypted => {
+                let bytes = BASE64_URL_SAFE_NO_PAD
+                    .decode(payload)
+                    .map_err(|_err| invalid("payload is not base64url"))?;
+                if bytes.len() <= NONCE_LEN {
+                    return Err(invalid("payload too short"));
+                }
+                let (nonce, encrypted) = bytes.split_at(NONCE_LEN);
+                aes::decrypt(
+                    &hex::encode(encrypted),
+                    &hex::encode(&self.key),
+                    Some(&hex::encode(nonce)),
+                    Some("chacha20-poly1305"),
+                    false,
+                )
+                .map_err(|_err| invalid("decryption failed"))?
+            }
+        };
+
+        serde_json::from_str(&json).map_err(|err| invalid(&err.to_string()))
+    }
+
+    fn is_trackable(href: &str) -> bool {
+        Url::parse(href.trim())
+            .map(|url| matches!(url.scheme(), "http" | "https"))
+            .unwrap_or(false)
+    }
+
+    // Returns the rewritten html and the tracked links, in document order.
+    pub(crate) fn rewrite(&self, html: &str) -> Result<(String, Vec<TrackedLink>), Error> {
+        let mut links = Vec::new();
+        let mut payload_error = None;
+
+        let rewritten = rewrite_str(
+            html,
+            RewriteStrSettings {
+                element_content_handlers: vec![element!("a[href]", |el| {
+                    if el.has_attribute("data-no-track") {
+                        return Ok(());
+                    }
+                    let href = el.get_attribute("href").unwrap_or_default();
+                    let href = html_attribute_decode(&href);
+                    if !Self::is_trackable(&href) {
+                        return Ok(());
+                    }
+
+                    let link = TrackedLink {
+                        url: href.trim().to_owned(),
+                        index: links.len(),
+                        recipient_id: self.recipient_id.clone(),
+                    };
+                    match self.payload(&link) {
+                        Ok(payload) => {
+                            el.set_attribute("href", &format!("{}{}", self.base_url, payload))?;
+                            links.push(link);
+                        }
+                        Err(err) => payload_error = Some(err),
+                    }
+                    Ok(())
+                })],
+                ..RewriteStrSettings::default()
+            },
+        )
+        .map_err(|err| Error::RenderingError(err.to_string()))?;
+
+        match payload_error {
+            Some(err) => Err(err),
+            None => Ok((rewritten, links)),
+        }
+    }
+}
+
+// The key is never printed.
+impl fmt::Debug for ClickTracking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClickTracking")
+            .field("base_url", &self.base_url)
+            .field("recipient_id", &self.recipient_id)
+            .field("protection", &self.protection)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE_URL: &str = "https://track.example/c/";
+    const HTML: &str = r##"<p><a href="https://shop.example.com/p/1?a=1&amp;b=2">Shop</a>
+<a href="mailto:help@shop.example.com">Mail</a> <a href="https://shop.example.com/unsubscribe" data-no-track>Unsubscribe</a>
+<a class="btn" href="http://example.org/">Other</a> <a href="#top">Top</a></p>"##;
+
+    fn tracked_links(recipient_id: &str) -> Vec<TrackedLink> {
+        vec![
+            TrackedLink {
+                url: "https://shop.example.com/p/1?a=1&b=2".to_owned(),
+                index: 0,
+                recipient_id: recipient_id.to_owned(),
+            },
+            TrackedLink {
+                url: "http://example.org/".to_owned(),
+                index: 1,
+                recipient_id: recipient_id.to_owned(),
+            },
+        ]
+    }
+
+    // Payloads of the rewritten links, in document order
+    fn payloads(html: &str) -> Vec<String> {
+        html.split(&format!("href=\"{}", BASE_URL))
+            .skip(1)
+            .map(|x| x.split('"').next().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn unit_signed_rewrite() {
+        let mut tracking = ClickTracking::signed(BASE_URL.to_owned(), b"secret".to_vec());
+        tracking.set_recipient_id("user-42".to_owned());
+
+        let (html, links) = tracking.rewrite(HTML).unwrap();
+        assert_eq!(links, tracked_links("user-42"));
+        assert!(html.contains(r#"<a href="mailto:help@shop.example.com">Mail</a>"#));
+        assert!(html.contains(r#"<a href="https://shop.example.com/unsubscribe" data-no-track>"#));
+        assert!(html.contains(r##"<a href="#top">Top</a>"##));
+        assert!(html.contains(r#"<a class="btn" href="https://track.example/c/"#));
+
+        let payloads = payloads(&html);
+        assert_eq!(
+            payloads[0],
+            "eyJ1IjoiaHR0cHM6Ly9zaG9wLmV4YW1wbGUuY29tL3AvMT9hPTEmYj0yIiwiaSI6MCwiciI6InVzZXItNDIifQ.JoZ4KqyQw5cCaviiKOLYuEOMMRp5nJS4j0gs52F5rg8"
+        );
+        let verified: Vec<TrackedLink> = payloads.iter().map(|x| tracking.verify(x).unwrap()).collect();
+        assert_eq!(verified, links);
+    }
+
+    #[test]
+    fn unit_rewrite_character_references() {
+        let tracking = ClickTracking::signed(BASE_URL.to_owned(), b"secret".to_vec());
+        for html in [
+            r#"<a href="https://shop.example.com/p/1?a=1&#38;b=2">Shop</a>"#,
+            r#"<a href="https://shop.example.com/p/1?a=1&#x26;b=2">Shop</a>"#,
+            r#"<a href="https://shop.example.com/p/1?a=1&amp;b=2">Shop</a>"#,
+        ] {
+            let (_, links) = tracking.rewrite(html).unwrap();
+            assert_eq!(links[0].url, "https://shop.example.com/p/1?a=1&b=2");
+        }
+        // Not a character reference in an attribute, as it is followed by =
+        let (_, links) = tracking.rewrite(r#"<a href="https://shop.example.com/p/1?a=1&copy=2">Shop</a>"#).unwrap();
+        assert_eq!(links[0].url, "https://shop.example.com/p/1?a=1&copy=2");
+    }
+
+    #[test]
+    fn unit_signed_verify() {
+        let tracking = ClickTracking::signed(BASE_URL.to_owned(), b"secret".to_vec());
+        let payload = tracking.payload(&tracked_links("user-42")[0]).unwrap();
+
+        assert_eq!(tracking.verify(&payload).unwrap(), tracked_links("user-42")[0]);
+
+        let other_key = ClickTracking::signed(BASE_URL.to_owned(), b"other".to_vec());
+        assert!(other_key.verify(&payload).is_err());
+
+        let (message, signature) = payload.split_once('.').unwrap();
+        let forged = BASE64_URL_SAFE_NO_PAD.encode(r#"{"u":"https://evil.example/","i":0,"r":"user-42"}"#);
+        assert!(tracking.verify(&format!("{}.{}", forged, signature)).is_err());
+        assert!(tracking.verify(message).is_err());
+        assert!(tracking.verify("").is_err());
+    }
+
+    #[test]
+    fn unit_encrypted_rewrite() {
+        let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f").unwrap();
+        let mut tracking = ClickTracking::encrypted(BASE_URL.to_owned(), key).unwrap();
+        tracking.set_recipient_id("user-42".to_owned());
+
+        let (html, links) = tracking.rewrite(HTML).unwrap();
+        assert_eq!(links, tracked_links("user-42"));
+
+        let payloads = payloads(&html);
+        assert!(!payloads[0].contains("eyJ1"));
+        let verified: Vec<TrackedLink> = payloads.iter().map(|x| tracking.verify(x).unwrap()).collect();
+        assert_eq!(verified, links);
+
+        let mut tampered = BASE64_URL_SAFE_NO_PAD.decode(&payloads[0]).unwrap();
+        tampered[NONCE_LEN] ^= 1;
+        assert!(tracking.verify(&BASE64_URL_SAFE_NO_PAD.encode(tampered)).is_err());
+
+        let other_key = ClickTracking::encrypted(BASE_URL.to_owned(), vec![7; 32]).unwrap();
+        assert!(other_key.verify(&payloads[0]).is_err());
+        assert!(ClickTracking::encrypted(BASE_URL.to_owned(), vec![7; 16]).is_err());
+    }
+}
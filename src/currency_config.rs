@@ -1,13 +1,20 @@
+use std::collections::HashMap;
+use std::sync::{PoisonError, RwLock};
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::error::Error;
 
 pub struct CurrencyConfig<'a> {
-    #[allow(dead_code)]
     pub value: &'a str,
-    #[allow(dead_code)]
     pub name: &'a str,
+    pub numeric_code: &'a str,
     pub symbol: &'a str,
     pub is_symbol_prefix: bool,
     pub separator: &'a str,
     pub delimiter: &'a str,
+    pub grouping: &'a [usize],
     pub precision: Option<i64>,
 }
 
@@ -16,3 +23,214 @@ include!(concat!(env!("OUT_DIR"), "/codegen.rs"));
 pub fn currency_config(value: &str) -> Option<&CurrencyConfig> {
     CURRENCY_CONFIG.get(value)
 }
+
+/// A currency definition loaded at runtime, in the format of config/currency.json.
+/// Fields left out fall back to the compiled table, so an override can be as small as
+/// {"value": "USD", "symbol": "US$"}.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CurrencyDefinition {
+    pub value: String,
+    pub name: Option<String>,
+    pub numeric_code: Option<String>,
+    pub symbol: Option<String>,
+    pub is_symbol_prefix: Option<bool>,
+    pub separator: Option<String>,
+    pub delimiter: Option<String>,
+    /// Digit group sizes, e.g. [3, 2] for lakh and crore.
+    pub grouping: Option<Vec<usize>>,
+    pub precision: Option<i64>,
+}
+
+impl CurrencyDefinition {
+    // Fields set on other win.
+    fn merge(&mut self, other: &CurrencyDefinition) {
+        fn set<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+            if other.is_some() {
+                field.clone_from(other);
+            }
+        }
+        set(&mut self.name, &other.name);
+        set(&mut self.numeric_code, &other.numeric_code);
+        set(&mut self.symbol, &other.symbol);
+        set(&mut self.is_symbol_prefix, &other.is_symbol_prefix);
+        set(&mut self.separator, &other.separator);
+        set(&mut self.delimiter, &other.delimiter);
+        set(&mut self.grouping, &other.grouping);
+        set(&mut self.precision, &other.precision);
+    }
+}
+
+impl From<&CurrencyConfig<'_>> for CurrencyDefinition {
+    fn from(config: &CurrencyConfig<'_>) -> Self {
+        Self {
+            value: config.value.to_owned(),
+            name: Some(config.name.to_owned()),
+            numeric_code: Some(config.numeric_code.to_owned()),
+            symbol: Some(config.symbol.to_owned()),
+            is_symbol_prefix: Some(config.is_symbol_prefix),
+            separator: Some(config.separator.to_owned()),
+            delimiter: Some(config.delimiter.to_owned()),
+            grouping: Some(config.grouping.to_vec()),
+            precision: config.precision,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CurrencyFile {
+    currency_types: Vec<CurrencyDefinition>,
+}
+
+/// Currency definitions keyed by their upper case code.
+#[derive(Clone, Debug, Default)]
+pub struct CurrencyTable {
+    currencies: HashMap<String, CurrencyDefinition>,
+}
+
+impl CurrencyTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads {"currency_types": [...]}, the format of config/currency.json.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let file: CurrencyFile = serde_json::from_str(json)
+            .map_err(|err| Error::JsonDecodeError(err.to_string()))?;
+
+        let mut table = Self::new();
+        for definition in file.currency_types {
+            table.insert(definition);
+        }
+        Ok(table)
+    }
+
+    /// Merges into the definition already in the table for the same currency, if any.
+    pub fn insert(&mut self, mut definition: CurrencyDefinition) {
+        definition.value = definition.value.to_ascii_uppercase();
+        match self.currencies.get_mut(&definition.value) {
+            Some(existing) => existing.merge(&definition),
+            None => {
+                self.currencies.insert(definition.value.clone(), definition);
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &CurrencyTable) {
+        for definition in other.currencies.values() {
+            self.insert(definition.clone());
+        }
+    }
+
+    pub fn get(&self, value: &str) -> Option<&CurrencyDefinition> {
+        self.currencies.get(&value.to_ascii_uppercase())
+    }
+}
+
+lazy_static! {
+    static ref RUNTIME_CURRENCIES: RwLock<CurrencyTable> = RwLock::new(CurrencyTable::new());
+}
+
+/// Replaces the currencies loaded at runtime. The compiled table stays as the fallback.
+pub fn load_currencies(table: CurrencyTable) {
+    *RUNTIME_CURRENCIES.write().unwrap_or_else(PoisonError::into_inner) = table;
+}
+
+/// Merges definitions into the currencies loaded at runtime.
+pub fn merge_currencies(table: &CurrencyTable) {
+    RUNTIME_CURRENCIES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .merge(table);
+}
+
+/// Looks a currency up in the compiled table, then in the currencies loaded at runtime
+/// and last in overrides, typically the table of the render context. Later definitions win field by field.
+pub(crate) fn resolve_currency(value: &str, overrides: Option<&CurrencyTable>) -> Option<CurrencyDefinition> {
+    let value = value.to_ascii_uppercase();
+    let mut resolved = currency_config(&value).map(CurrencyDefinition::from);
+
+    let runtime = RUNTIME_CURRENCIES.read().unwrap_or_else(PoisonError::into_inner);
+    for table in [Some(&*runtime), overrides].into_iter().flatten() {
+        if let Some(definition) = table.get(&value) {
+            match resolved.as_mut() {
+                Some(resolved) => resolved.merge(definition),
+                None => resolved = Some(definition.clone()),
+            }
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_currency_config() {
+        let usd = currency_config("USD").unwrap();
+        assert_eq!((usd.name, usd.numeric_code, usd.precision), ("US Dollar", "840", Some(2)));
+        assert_eq!(currency_config("JPY").unwrap().precision, Some(0));
+        assert_eq!(currency_config("KRW").unwrap().precision, Some(0));
+        assert_eq!(currency_config("KWD").unwrap().precision, Some(3));
+        assert_eq!(currency_config("BHD").unwrap().precision, Some(3));
+        assert_eq!(currency_config("CLF").unwrap().precision, Some(4));
+        assert_eq!(currency_config("XAU").unwrap().precision, None);
+        assert_eq!(currency_config("ALL").unwrap().numeric_code, "008");
+        assert_eq!(currency_config("ZWG").unwrap().name, "Zimbabwe Gold");
+        assert_eq!(currency_config("XCG").unwrap().numeric_code, "532");
+
+        let mru = currency_config("MRU").unwrap();
+        assert_eq!((mru.symbol, mru.is_symbol_prefix), ("MRU", false));
+        assert_eq!(currency_config("LTL").unwrap().symbol, "Lt");
+        assert_eq!(currency_config("INR").unwrap().grouping, &[3, 2]);
+        assert_eq!(currency_config("EUR").unwrap().grouping, &[3]);
+    }
+
+    #[test]
+    fn unit_resolve_currency() {
+        let usd = resolve_currency("usd", None).unwrap();
+        assert_eq!(usd.symbol.as_deref(), Some("$"));
+        assert_eq!(usd.is_symbol_prefix, Some(true));
+        assert_eq!(resolve_currency("XYZ", None), None);
+
+        let tenant = CurrencyTable::from_json(r#"{"currency_types": [
+            {"value": "usd", "symbol": "US$"},
+            {"value": "XYZ", "name": "Test Currency", "symbol": "T", "is_symbol_prefix": false, "separator": " ", "delimiter": ",", "precision": 3}
+        ]}"#).unwrap();
+
+        let usd = resolve_currency("USD", Some(&tenant)).unwrap();
+        assert_eq!(usd.symbol.as_deref(), Some("US$"));
+        assert_eq!(usd.separator.as_deref(), Some(","));
+        assert_eq!(usd.name.as_deref(), Some("US Dollar"));
+
+        let xyz = resolve_currency("xyz", Some(&tenant)).unwrap();
+        assert_eq!(xyz.symbol.as_deref(), Some("T"));
+        assert_eq!(xyz.precision, Some(3));
+
+        assert!(CurrencyTable::from_json(r#"{"currency_types": [{"value": 1}]}"#).is_err());
+        assert!(CurrencyTable::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn unit_runtime_currencies() {
+        let mut table = CurrencyTable::new();
+        table.insert(CurrencyDefinition {
+            value: "XXA".to_owned(),
+            symbol: Some("A$".to_owned()),
+            ..Default::default()
+        });
+        merge_currencies(&table);
+        assert_eq!(resolve_currency("XXA", None).unwrap().symbol.as_deref(), Some("A$"));
+
+        let mut overrides = CurrencyTable::new();
+        overrides.insert(CurrencyDefinition {
+            value: "xxa".to_owned(),
+            precision: Some(0),
+            ..Default::default()
+        });
+        let resolved = resolve_currency("XXA", Some(&overrides)).unwrap();
+        assert_eq!(resolved.symbol.as_deref(), Some("A$"));
+        assert_eq!(resolved.precision, Some(0));
+    }
+}
@@ -16,5 +16,7 @@ pub enum Error {
     InternalError(String),
     #[error("Error decoding json string: {0}")]
     JsonDecodeError(String),
+    #[error("invalid tracking payload: {0}")]
+    InvalidTrackingPayload(String),
 
 }
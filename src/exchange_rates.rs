@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use serde_json::Value;
+
+use crate::error::Error;
+
+// Conversions between two non base currencies divide, and are rounded to this many decimals.
+const CROSS_RATE_SCALE: i64 = 10;
+
+/// Exchange rates against a base currency: one unit of the base is worth rate units of a currency.
+/// Rates are kept as exact decimals. Hosts that fetch rates over http should parse them once and
+/// share the table across renders, it is immutable once set on the render context.
+#[derive(Clone, Debug, Default)]
+pub struct ExchangeRates {
+    base: String,
+    rates: HashMap<String, BigDecimal>,
+}
+
+impl ExchangeRates {
+    pub fn new(base: String) -> Self {
+        Self {
+            base: base.to_ascii_uppercase(),
+            ..Default::default()
+        }
+    }
+
+    /// Reads {"base": "USD", "rates": {"EUR": "0.9215", "JPY": 151.37}}.
+    /// Rates may be strings or numbers, strings keep every digit.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let invalid = |cause: String| Error::JsonDecodeError(cause);
+
+        let value: Value = serde_json::from_str(json).map_err(|err| invalid(err.to_string()))?;
+        let base = value["base"].as_str().ok_or_else(|| invalid("base currency expected".to_owned()))?;
+        let rates = value["rates"].as_object().ok_or_else(|| invalid("rates object expected".to_owned()))?;
+
+        let mut table = Self::new(base.to_owned());
+        for (currency, rate) in rates {
+            let rate = match rate {
+                Value::String(rate) => rate.clone(),
+                Value::Number(rate) => rate.to_string(),
+                _ => return Err(invalid(format!("rate of {} is not a number", currency))),
+            };
+            table.insert(currency.to_owned(), &rate)?;
+        }
+        Ok(table)
+    }
+
+    pub fn insert(&mut self, currency: String, rate: &str) -> Result<(), Error> {
+        let rate = BigDecimal::from_str(rate.trim())
+            .ok()
+            .filter(|x| x > &BigDecimal::default())
+            .ok_or_else(|| Error::JsonDecodeError(format!("rate of {} is not a positive number", currency)))?;
+        self.rates.insert(currency.to_ascii_uppercase(), rate);
+        Ok(())
+    }
+
+    fn rate(&self, currency: &str) -> Option<BigDecimal> {
+        if currency == self.base {
+            return Some(BigDecimal::from(1));
+        }
+        self.rates.get(currency).cloned()
+    }
+
+    /// None when a currency has no rate.
+    pub(crate) fn convert(&self, amount: &BigDecimal, from: &str, to: &str) -> Option<BigDecimal> {
+        let from = from.to_ascii_uppercase();
+        let to = to.to_ascii_uppercase();
+        if from == to {
+            return Some(amount.normalized());
+        }
+
+        let converted = amount * self.rate(&to)?;
+        if from == self.base {
+            return Some(converted.normalized());
+        }
+        Some((converted / self.rate(&from)?).round(CROSS_RATE_SCALE).normalized())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_convert() {
+        let rates = ExchangeRates::from_json(r#"{"base": "USD", "rates": {"EUR": "0.9215", "jpy": 151.37, "GBP": "0.79"}}"#).unwrap();
+        let convert = |amount: &str, from: &str, to: &str| {
+            rates
+                .convert(&BigDecimal::from_str(amount).unwrap(), from, to)
+                .map(|x| x.to_string())
+        };
+
+        assert_eq!(convert("100", "USD", "EUR").as_deref(), Some("92.15"));
+        assert_eq!(convert("19.99", "usd", "JPY").as_deref(), Some("3025.8863"));
+        assert_eq!(convert("1000", "JPY", "USD").as_deref(), Some("6.6063288631"));
+        assert_eq!(convert("100", "EUR", "GBP").as_deref(), Some("85.7297883885"));
+        assert_eq!(convert("10.50", "EUR", "EUR").as_deref(), Some("10.5"));
+        assert_eq!(convert("100", "USD", "CHF"), None);
+        assert_eq!(convert("100", "CHF", "USD"), None);
+
+        assert!(ExchangeRates::from_json(r#"{"rates": {}}"#).is_err());
+        assert!(ExchangeRates::from_json(r#"{"base": "USD", "rates": {"EUR": "abc"}}"#).is_err());
+        assert!(ExchangeRates::from_json(r#"{"base": "USD", "rates": {"EUR": 0}}"#).is_err());
+    }
+}
@@ -7,14 +7,70 @@ use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
 use liquid_core::{Value, ValueView};
 
 
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit, KeyInit};
+use aes::cipher::{block_padding::Pkcs7, BlockCipher, BlockDecryptMut, BlockEncryptMut, KeyIvInit, KeyInit, StreamCipher};
+use phf::phf_map;
 use super::{invalid_argument, invalid_input};
 
-type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
-type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cipher {
+    Aes128Cbc,
+    Aes192Cbc,
+    Aes256Cbc,
+    Aes128Ecb,
+    Aes192Ecb,
+    Aes256Ecb,
+    Aes128Ctr,
+    Aes256Ctr,
+    ChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn key_len(self) -> usize {
+        match self {
+            Cipher::Aes128Cbc | Cipher::Aes128Ecb | Cipher::Aes128Ctr => 16,
+            Cipher::Aes192Cbc | Cipher::Aes192Ecb => 24,
+            Cipher::Aes256Cbc | Cipher::Aes256Ecb | Cipher::Aes256Ctr | Cipher::ChaCha20Poly1305 => 32,
+        }
+    }
+
+    // ECB takes no iv, chacha20-poly1305 takes a 96 bit nonce.
+    fn iv_len(self) -> usize {
+        match self {
+            Cipher::Aes128Ecb | Cipher::Aes192Ecb | Cipher::Aes256Ecb => 0,
+            Cipher::ChaCha20Poly1305 => 12,
+            _ => 16,
+        }
+    }
+}
 
-type Aes256EcbEnc = ecb::Encryptor<aes::Aes256>;
-type Aes256EcbDec = ecb::Decryptor<aes::Aes256>;
+// Names follow openssl's cipher names, so templates ported from ruby keep working.
+static CIPHERS: phf::Map<&'static str, Cipher> = phf_map! {
+    "aes-128-cbc" => Cipher::Aes128Cbc,
+    "aes-192-cbc" => Cipher::Aes192Cbc,
+    "aes-256-cbc" => Cipher::Aes256Cbc,
+    "aes-128-ecb" => Cipher::Aes128Ecb,
+    "aes-192-ecb" => Cipher::Aes192Ecb,
+    "aes-256-ecb" => Cipher::Aes256Ecb,
+    "aes-128-ctr" => Cipher::Aes128Ctr,
+    "aes-256-ctr" => Cipher::Aes256Ctr,
+    "chacha20-poly1305" => Cipher::ChaCha20Poly1305,
+};
+
+fn cipher(cipher_name: Option<&str>) -> Result<Cipher> {
+    let name = cipher_name.unwrap_or_default().to_ascii_lowercase();
+    if name.is_empty() {
+        return Ok(Cipher::Aes256Cbc);
+    }
+    CIPHERS.get(name.as_str()).copied().ok_or_else(|| {
+        invalid_argument(
+            "cipher_name".to_owned(),
+            format!("Unsupported cipher {}", name),
+        )
+    })
+}
 
 
 //   def aes256_encrypt_v2(data, key, iv=nil, cipher_name=nil)
@@ -25,110 +81,168 @@ type Aes256EcbDec = ecb::Decryptor<aes::Aes256>;
 //     binary_string_to_hex(output)
 //   end
 
-fn aes256_encrypt(data: &str, key_hex: &str, iv_hex: Option<&str>, cipher_name: Option<&str>, truncate_iv_v1: bool) -> Result<String> {
+fn cipher_key(key_hex: &str, cipher: Cipher) -> Result<Vec<u8>> {
     let key = hex::decode(key_hex).map_err(|_err|{
         invalid_argument(
             key_hex.to_owned(),
             "Hex decoding key error".to_owned(),
         )
     })?;
-    if let Some("aes-256-ecb") = cipher_name {
-        let res = Aes256EcbEnc::new_from_slice(key.as_slice()).unwrap()
-            .encrypt_padded_vec_mut::<Pkcs7>(data.as_bytes());
-        let encoded = hex::encode(res);
-        return Ok(encoded);
+    if key.len() != cipher.key_len() {
+        return Err(invalid_argument(
+            "key_hex".to_owned(),
+            format!("Expected a {} byte key, got {} bytes", cipher.key_len(), key.len()),
+        ));
     }
+    Ok(key)
+}
 
-    // aes-256-cbc
-    if iv_hex.is_none() {
-        return Err(liquid_core::Error::with_msg("Missing required argument iv"));
+fn cipher_iv(iv_hex: Option<&str>, cipher: Cipher, truncate_iv_v1: bool) -> Result<Vec<u8>> {
+    if cipher.iv_len() == 0 {
+        return Ok(Vec::new());
     }
-    let iv_hex = iv_hex.unwrap();
-    let mut iv_str = iv_hex.to_owned();
-    let decoded: Vec<u8>;
+    let iv_hex = iv_hex.ok_or_else(|| liquid_core::Error::with_msg("Missing required argument iv"))?;
     let iv = if truncate_iv_v1 {
+        // v1 used the first 16 characters of the hex string as the raw iv
         let upto = iv_hex.char_indices().map(|(i, _)| i).nth(16).unwrap_or(iv_hex.len());
-        iv_str.truncate(upto);
-        iv_str.as_bytes()
+        iv_hex.as_bytes()[..upto].to_vec()
     } else {
-        decoded = hex::decode(iv_hex).map_err(|_err|{
+        hex::decode(iv_hex).map_err(|_err|{
             invalid_argument(
                 iv_hex.to_owned(),
                 "Hex decoding iv error".to_owned(),
             )
-        })?;
-        decoded.as_slice()
+        })?
+    };
+    if iv.len() != cipher.iv_len() {
+        return Err(invalid_argument(
+            "iv_hex".to_owned(),
+            format!("Expected a {} byte iv, got {} bytes", cipher.iv_len(), iv.len()),
+        ));
+    }
+    Ok(iv)
+}
+
+fn cbc_encrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8>
+where
+    C: BlockEncryptMut + BlockCipher + KeyInit,
+{
+    // key and iv lengths are validated by cipher_key and cipher_iv
+    cbc::Encryptor::<C>::new_from_slices(key, iv).unwrap()
+        .encrypt_padded_vec_mut::<Pkcs7>(data)
+}
+
+fn cbc_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>>
+where
+    C: BlockDecryptMut + BlockCipher + KeyInit,
+{
+    cbc::Decryptor::<C>::new_from_slices(key, iv).unwrap()
+        .decrypt_padded_vec_mut::<Pkcs7>(data).map_err(|err|{
+            invalid_input(format!("decryption error: {}", err))
+        })
+}
+
+fn ecb_encrypt<C>(key: &[u8], data: &[u8]) -> Vec<u8>
+where
+    C: BlockEncryptMut + BlockCipher + KeyInit,
+{
+    ecb::Encryptor::<C>::new_from_slice(key).unwrap()
+        .encrypt_padded_vec_mut::<Pkcs7>(data)
+}
+
+fn ecb_decrypt<C>(key: &[u8], data: &[u8]) -> Result<Vec<u8>>
+where
+    C: BlockDecryptMut + BlockCipher + KeyInit,
+{
+    ecb::Decryptor::<C>::new_from_slice(key).unwrap()
+        .decrypt_padded_vec_mut::<Pkcs7>(data).map_err(|err|{
+            invalid_input(format!("decryption error: {}", err))
+        })
+}
+
+// CTR is symmetric, the same keystream encrypts and decrypts.
+fn ctr_apply<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Vec<u8>
+where
+    C: KeyIvInit + StreamCipher,
+{
+    let mut buf = data.to_vec();
+    C::new_from_slices(key, iv).unwrap()
+        .apply_keystream(&mut buf);
+    buf
+}
+
+fn chacha20_poly1305_key(key: &[u8]) -> ring::aead::LessSafeKey {
+    let unbound = ring::aead::UnboundKey::new(&ring::aead::CHACHA20_POLY1305, key).unwrap();
+    ring::aead::LessSafeKey::new(unbound)
+}
+
+// Output is the ciphertext followed by the 16 byte poly1305 tag.
+fn chacha20_poly1305_encrypt(key: &[u8], nonce: &[u8], data: &[u8]) -> Vec<u8> {
+    let nonce = ring::aead::Nonce::try_assume_unique_for_key(nonce).unwrap();
+    let mut buf = data.to_vec();
+    chacha20_poly1305_key(key)
+        .seal_in_place_append_tag(nonce, ring::aead::Aad::empty(), &mut buf)
+        .unwrap();
+    buf
+}
+
+fn chacha20_poly1305_decrypt(key: &[u8], nonce: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let nonce = ring::aead::Nonce::try_assume_unique_for_key(nonce).unwrap();
+    let mut buf = data.to_vec();
+    let plain_len = chacha20_poly1305_key(key)
+        .open_in_place(nonce, ring::aead::Aad::empty(), &mut buf)
+        .map_err(|_err| invalid_input("decryption error: authentication failed"))?
+        .len();
+    buf.truncate(plain_len);
+    Ok(buf)
+}
+
+pub(crate) fn encrypt(data: &str, key_hex: &str, iv_hex: Option<&str>, cipher_name: Option<&str>, truncate_iv_v1: bool) -> Result<String> {
+    let cipher = cipher(cipher_name)?;
+    let key = cipher_key(key_hex, cipher)?;
+    let iv = cipher_iv(iv_hex, cipher, truncate_iv_v1)?;
+    let data = data.as_bytes();
+
+    let res = match cipher {
+        Cipher::Aes128Cbc => cbc_encrypt::<aes::Aes128>(&key, &iv, data),
+        Cipher::Aes192Cbc => cbc_encrypt::<aes::Aes192>(&key, &iv, data),
+        Cipher::Aes256Cbc => cbc_encrypt::<aes::Aes256>(&key, &iv, data),
+        Cipher::Aes128Ecb => ecb_encrypt::<aes::Aes128>(&key, data),
+        Cipher::Aes192Ecb => ecb_encrypt::<aes::Aes192>(&key, data),
+        Cipher::Aes256Ecb => ecb_encrypt::<aes::Aes256>(&key, data),
+        Cipher::Aes128Ctr => ctr_apply::<Aes128Ctr>(&key, &iv, data),
+        Cipher::Aes256Ctr => ctr_apply::<Aes256Ctr>(&key, &iv, data),
+        Cipher::ChaCha20Poly1305 => chacha20_poly1305_encrypt(&key, &iv, data),
     };
-    let res = Aes256CbcEnc::new_from_slices(key.as_slice(), iv).unwrap()
-        .encrypt_padded_vec_mut::<Pkcs7>(data.as_bytes());
 
     let encoded = hex::encode(res);
     Ok(encoded)
-
 }
 
 
-fn aes256_decrypt(data: &str, key_hex: &str, iv_hex: Option<&str>, cipher_name: Option<&str>, truncate_iv_v1: bool) -> Result<String> {
+pub(crate) fn decrypt(data: &str, key_hex: &str, iv_hex: Option<&str>, cipher_name: Option<&str>, truncate_iv_v1: bool) -> Result<String> {
     let data = hex::decode(data).map_err(|_err|{
         invalid_argument(
             data.to_owned(),
             "Hex decoding data error".to_owned(),
         )
     })?;
-    let key = hex::decode(key_hex).map_err(|_err|{
-        invalid_argument(
-            key_hex.to_owned(),
-            "Hex decoding key error".to_owned(),
-        )
-    })?;
-
-    if let Some("aes-256-ecb") = cipher_name {
-        let res = Aes256EcbDec::new_from_slice(key.as_slice()).unwrap()
-            .decrypt_padded_vec_mut::<Pkcs7>(&data).map_err(|err|{
-                invalid_argument(
-                    key_hex.to_owned(),
-                    format!("decryption error: {}", err),
-                )
-            })?;
-
-        let decoded = String::from_utf8(res).map_err(|err| {
-            invalid_input(
-                format!("Decrypted data is not utf8. error: {}", err),
-            )
-        })?;
-        return Ok(decoded);
-    }
-
-    // aes-256-cbc
-    if iv_hex.is_none() {
-        return Err(liquid_core::Error::with_msg("Missing required argument iv"));
-    }
-
-    let iv_hex = iv_hex.unwrap();
-    let decoded: Vec<u8>;
-    let mut iv_str = iv_hex.to_owned();
-    let iv = if truncate_iv_v1 {
-        let upto = iv_hex.char_indices().map(|(i, _)| i).nth(16).unwrap_or(iv_hex.len());
-        iv_str.truncate(upto);
-        iv_str.as_bytes()
-    } else {
-        decoded = hex::decode(iv_hex).map_err(|_err|{
-            invalid_argument(
-                iv_hex.to_owned(),
-                "Hex decoding iv error".to_owned(),
-            )
-        })?;
-        decoded.as_slice()
+    let cipher = cipher(cipher_name)?;
+    let key = cipher_key(key_hex, cipher)?;
+    let iv = cipher_iv(iv_hex, cipher, truncate_iv_v1)?;
+
+    let res = match cipher {
+        Cipher::Aes128Cbc => cbc_decrypt::<aes::Aes128>(&key, &iv, &data)?,
+        Cipher::Aes192Cbc => cbc_decrypt::<aes::Aes192>(&key, &iv, &data)?,
+        Cipher::Aes256Cbc => cbc_decrypt::<aes::Aes256>(&key, &iv, &data)?,
+        Cipher::Aes128Ecb => ecb_decrypt::<aes::Aes128>(&key, &data)?,
+        Cipher::Aes192Ecb => ecb_decrypt::<aes::Aes192>(&key, &data)?,
+        Cipher::Aes256Ecb => ecb_decrypt::<aes::Aes256>(&key, &data)?,
+        Cipher::Aes128Ctr => ctr_apply::<Aes128Ctr>(&key, &iv, &data),
+        Cipher::Aes256Ctr => ctr_apply::<Aes256Ctr>(&key, &iv, &data),
+        Cipher::ChaCha20Poly1305 => chacha20_poly1305_decrypt(&key, &iv, &data)?,
     };
 
-    let res = Aes256CbcDec::new_from_slices(key.as_slice(), iv).unwrap()
-        .decrypt_padded_vec_mut::<Pkcs7>(&data).map_err(|err|{
-            invalid_argument(
-                key_hex.to_owned(),
-                format!("decryption error: {}", err),
-            )
-        })?;
-
     let decoded = String::from_utf8(res).map_err(|err| {
         invalid_input(
             format!("Decrypted data is not utf8. error: {}", err),
@@ -147,7 +261,7 @@ struct Aes256Args {
     #[parameter(description = "Hex encoded initialization vector.", arg_type = "str")]
     iv_hex: Option<Expression>,
 
-    #[parameter(description = "Cipher name. aes-256-cbc or aes-256-ecb. Default is aes-256-cbc", arg_type = "str")]
+    #[parameter(description = "Cipher name. aes-128-cbc, aes-192-cbc, aes-256-cbc, aes-128-ecb, aes-192-ecb, aes-256-ecb, aes-128-ctr, aes-256-ctr or chacha20-poly1305. Default is aes-256-cbc", arg_type = "str")]
     cipher_name: Option<Expression>,
 }
 
@@ -179,7 +293,7 @@ impl Filter for Aes256EncryptV2Filter {
         let iv_hex = args.iv_hex.map(|x| x.to_string());
         let cipher_name = args.cipher_name.map(|x| x.to_string());
 
-        let encoded = aes256_encrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), false)?;
+        let encoded = encrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), false)?;
         Ok(Value::scalar(encoded))
     }
 }
@@ -212,7 +326,7 @@ impl Filter for Aes256EncryptV1Filter {
         let iv_hex = args.iv_hex.map(|x| x.to_string());
         let cipher_name = args.cipher_name.map(|x| x.to_string());
 
-        let encoded = aes256_encrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), true)?;
+        let encoded = encrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), true)?;
         Ok(Value::scalar(encoded))
     }
 }
@@ -245,7 +359,7 @@ impl Filter for Aes256DecryptV2Filter {
         let iv_hex = args.iv_hex.map(|x| x.to_string());
         let cipher_name = args.cipher_name.map(|x| x.to_string());
 
-        let encoded = aes256_decrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), false)?;
+        let encoded = decrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), false)?;
         Ok(Value::scalar(encoded))
     }
 }
@@ -278,7 +392,7 @@ impl Filter for Aes256DecryptV1Filter {
         let iv_hex = args.iv_hex.map(|x| x.to_string());
         let cipher_name = args.cipher_name.map(|x| x.to_string());
 
-        let encoded = aes256_decrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), true)?;
+        let encoded = decrypt(s.as_str(), key_hex, iv_hex.as_deref(), cipher_name.as_deref(), true)?;
         Ok(Value::scalar(encoded))
     }
 }
@@ -420,6 +534,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unit_aes_cipher_names() {
+        let data = "testuser@getblueshift.com";
+        let iv = "7bdc922b354cc8fa8d3f2910ba7cc411";
+        let cases = [
+            ("aes-128-cbc", "9cc25c7879fc94d5a19eeb8e47573b84", iv, "6ee80b0d4946c114ed55d8d05088553a74861e8b6f0fa4da4d5db120e2737863"),
+            ("aes-192-cbc", "9cc25c7879fc94d5a19eeb8e47573b8423becb608a9a4e9d", iv, "a83150ac2599aafa689a8096cb41400284d0cde830322aca9e75af03c19827cf"),
+            ("aes-128-ecb", "9cc25c7879fc94d5a19eeb8e47573b84", "", "637f114907a713030d2ecc14c2dfb71f183b0653b9af747bc4c488a284448bb7"),
+            ("aes-256-ctr", "9cc25c7879fc94d5a19eeb8e47573b8423becb608a9a4e9d3c25c20aa7e04357", iv, "4bdfd4cac6962509fbf66e2bd5c06960c20d3b87dc51bae9bd"),
+            ("chacha20-poly1305", "9cc25c7879fc94d5a19eeb8e47573b8423becb608a9a4e9d3c25c20aa7e04357", "7bdc922b354cc8fa8d3f2910", "e756ea3bf0d42408507f42d12f66b4d689e733a793efba781b5e125e2c5f5d4429102e6129f58524d9"),
+        ];
+
+        for (cipher_name, key, iv, encrypted) in cases {
+            assert_eq!(
+                liquid_core::call_filter!(Aes256EncryptV2, data, key, iv, cipher_name).unwrap(),
+                liquid_core::value!(encrypted)
+            );
+            assert_eq!(
+                liquid_core::call_filter!(Aes256DecryptV2, encrypted, key, iv, cipher_name).unwrap(),
+                liquid_core::value!(data)
+            );
+        }
+    }
 
-}
+    #[test]
+    fn unit_aes_invalid_arguments() {
+        // aes-128 key with an aes-256 cipher
+        assert!(liquid_core::call_filter!(Aes256EncryptV2,
+            "testuser@getblueshift.com",
+            "9cc25c7879fc94d5a19eeb8e47573b84",
+            "7bdc922b354cc8fa8d3f2910ba7cc411",
+            "aes-256-cbc"
+        ).is_err());
+
+        assert!(liquid_core::call_filter!(Aes256EncryptV2,
+            "testuser@getblueshift.com",
+            "9cc25c7879fc94d5a19eeb8e47573b84",
+            "7bdc922b354cc8fa8d3f2910ba7cc411",
+            "des-ede3-cbc"
+        ).is_err());
+
+        // tampered chacha20-poly1305 ciphertext fails authentication
+        assert!(liquid_core::call_filter!(Aes256DecryptV2,
+            "f756ea3bf0d42408507f42d12f66b4d689e733a793efba781b5e125e2c5f5d4429102e6129f58524d9",
+            "9cc25c7879fc94d5a19eeb8e47573b8423becb608a9a4e9d3c25c20aa7e04357",
+            "7bdc922b354cc8fa8d3f2910",
+            "chacha20-poly1305"
+        ).is_err());
+    }
 
+}
@@ -0,0 +1,282 @@
+#![allow(clippy::invisible_characters)]
+
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
+use liquid_core::{Value, ValueView};
+
+use super::hashing::sha256;
+
+// Domains where gmail ignores dots in the local part.
+static GMAIL_DOMAINS: phf::Set<&'static str> = phf::phf_set! {
+    "gmail.com",
+    "googlemail.com",
+};
+
+pub(crate) fn email_normalize(email: &str, strip_dots: bool, strip_plus: bool) -> String {
+    let email = email.trim().to_lowercase();
+    let (local, domain) = match email.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return email,
+    };
+
+    let local = if strip_plus {
+        local.split('+').next().unwrap_or_default()
+    } else {
+        local
+    };
+    let local = if strip_dots && GMAIL_DOMAINS.contains(domain) {
+        local.replace('.', "")
+    } else {
+        local.to_owned()
+    };
+
+    format!("{}@{}", local, domain)
+}
+
+// Returns an empty string when the number can't be made into a valid E.164 number.
+pub(crate) fn phone_normalize(phone: &str, country_code: Option<&str>) -> String {
+    let phone = phone.trim();
+    let digits: String = phone.chars().filter(|x| x.is_ascii_digit()).collect();
+
+    let e164 = if phone.starts_with('+') {
+        digits
+    } else if let Some(international) = digits.strip_prefix("00") {
+        international.to_owned()
+    } else if let Some(country_code) = country_code {
+        let country_code: String = country_code.chars().filter(|x| x.is_ascii_digit()).collect();
+        // A number with the national trunk prefix is national, e.g. 020 7946 0958 -> +44 20 7946 0958.
+        // Without it, the number may already start with the country code.
+        let national = digits.trim_start_matches('0');
+        if national.len() == digits.len() && national.starts_with(&country_code) && national.len() > 10 {
+            national.to_owned()
+        } else {
+            format!("{}{}", country_code, national)
+        }
+    } else {
+        digits
+    };
+
+    if e164.len() < 8 || e164.len() > 15 || e164.starts_with('0') {
+        return String::new();
+    }
+    format!("+{}", e164)
+}
+
+#[derive(Debug, FilterParameters)]
+struct EmailNormalizeArgs {
+    #[parameter(description = "Removes dots from the local part of gmail addresses.", arg_type = "bool", mode = "keyword")]
+    strip_dots: Option<Expression>,
+
+    #[parameter(description = "Removes +tags from the local part.", arg_type = "bool", mode = "keyword")]
+    strip_plus: Option<Expression>,
+}
+
+/// email_normalize
+/// Trims and lowercases an email address, optionally removing gmail dots and +tags.
+/// Example:
+/// {{ " John.Doe+promo@GMail.com " | email_normalize: strip_dots: true, strip_plus: true }}
+/// Returns:
+/// johndoe@gmail.com
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "email_normalize",
+    description = "Normalizes an email address for audience matching.",
+    parameters(EmailNormalizeArgs),
+    parsed(EmailNormalizeFilter)
+)]
+pub struct EmailNormalize;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "email_normalize"]
+struct EmailNormalizeFilter {
+    #[parameters]
+    args: EmailNormalizeArgs,
+}
+
+impl Filter for EmailNormalizeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let normalized = email_normalize(
+            s.as_str(),
+            args.strip_dots.unwrap_or(false),
+            args.strip_plus.unwrap_or(false),
+        );
+        Ok(Value::scalar(normalized))
+    }
+}
+
+/// hashed_email
+/// Returns the hex encoded sha-256 of the normalized email address.
+/// Takes the same arguments as email_normalize.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hashed_email",
+    description = "Computes sha-256 of a normalized email address.",
+    parameters(EmailNormalizeArgs),
+    parsed(HashedEmailFilter)
+)]
+pub struct HashedEmail;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hashed_email"]
+struct HashedEmailFilter {
+    #[parameters]
+    args: EmailNormalizeArgs,
+}
+
+impl Filter for HashedEmailFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let normalized = email_normalize(
+            s.as_str(),
+            args.strip_dots.unwrap_or(false),
+            args.strip_plus.unwrap_or(false),
+        );
+        if normalized.is_empty() {
+            return Ok(Value::scalar(String::new()));
+        }
+        let encoded = data_encoding::HEXLOWER.encode(&sha256(normalized.as_bytes()));
+        Ok(Value::scalar(encoded))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct PhoneNormalizeArgs {
+    #[parameter(description = "Country calling code used for numbers without one, e.g. 1 or 91.", arg_type = "str", mode = "keyword")]
+    country_code: Option<Expression>,
+}
+
+/// phone_normalize
+/// Converts a phone number to E.164. Returns an empty string for invalid numbers.
+/// Example:
+/// {{ "(415) 555-2671" | phone_normalize: country_code: "1" }}
+/// Returns:
+/// +14155552671
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "phone_normalize",
+    description = "Normalizes a phone number to E.164.",
+    parameters(PhoneNormalizeArgs),
+    parsed(PhoneNormalizeFilter)
+)]
+pub struct PhoneNormalize;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "phone_normalize"]
+struct PhoneNormalizeFilter {
+    #[parameters]
+    args: PhoneNormalizeArgs,
+}
+
+impl Filter for PhoneNormalizeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let country_code = args.country_code.map(|x| x.to_string());
+        let normalized = phone_normalize(s.as_str(), country_code.as_deref());
+        Ok(Value::scalar(normalized))
+    }
+}
+
+/// hashed_phone
+/// Returns the hex encoded sha-256 of the E.164 phone number.
+/// Takes the same arguments as phone_normalize.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hashed_phone",
+    description = "Computes sha-256 of a phone number normalized to E.164.",
+    parameters(PhoneNormalizeArgs),
+    parsed(HashedPhoneFilter)
+)]
+pub struct HashedPhone;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hashed_phone"]
+struct HashedPhoneFilter {
+    #[parameters]
+    args: PhoneNormalizeArgs,
+}
+
+impl Filter for HashedPhoneFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let country_code = args.country_code.map(|x| x.to_string());
+        let normalized = phone_normalize(s.as_str(), country_code.as_deref());
+        if normalized.is_empty() {
+            return Ok(Value::scalar(String::new()));
+        }
+        let encoded = data_encoding::HEXLOWER.encode(&sha256(normalized.as_bytes()));
+        Ok(Value::scalar(encoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_email_normalize() {
+        assert_eq!(email_normalize(" John.Doe+promo@GMail.com ", false, false), "john.doe+promo@gmail.com");
+        assert_eq!(email_normalize(" John.Doe+promo@GMail.com ", true, false), "johndoe+promo@gmail.com");
+        assert_eq!(email_normalize(" John.Doe+promo@GMail.com ", true, true), "johndoe@gmail.com");
+        assert_eq!(email_normalize("John.Doe+promo@example.com", true, true), "john.doe@example.com");
+        assert_eq!(email_normalize("not an email", true, true), "not an email");
+        assert_eq!(email_normalize("", true, true), "");
+    }
+
+    #[test]
+    fn unit_phone_normalize() {
+        assert_eq!(phone_normalize("+1 (415) 555-2671", None), "+14155552671");
+        assert_eq!(phone_normalize("(415) 555-2671", Some("1")), "+14155552671");
+        assert_eq!(phone_normalize("14155552671", Some("+1")), "+14155552671");
+        assert_eq!(phone_normalize("0044 20 7946 0958", None), "+442079460958");
+        assert_eq!(phone_normalize("020 7946 0958", Some("44")), "+442079460958");
+        assert_eq!(phone_normalize("0491 12345678", Some("49")), "+4949112345678");
+        assert_eq!(phone_normalize("4949112345678", Some("49")), "+4949112345678");
+        assert_eq!(phone_normalize("98765 43210", Some("91")), "+919876543210");
+        assert_eq!(phone_normalize("555-2671", None), "");
+        assert_eq!(phone_normalize("", Some("1")), "");
+    }
+
+    #[test]
+    fn unit_hashed_email() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HashedEmail, " TestUser@getblueshift.com", [], {}).unwrap(),
+            liquid_core::value!("5c2d9bfc1df73b5aa4b1310a5e0fbf2cb781fd68a51939123efb52b4df964554")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HashedEmail, "Test.User+news@gmail.com", [], {"strip_dots" => true, "strip_plus" => true}).unwrap(),
+            liquid_core::value!("dae9c7c55697ba170d6b494c458649bd469af525520280d0dcfc98d74d13b17e")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HashedEmail, "", [], {}).unwrap(),
+            liquid_core::value!("")
+        );
+    }
+
+    #[test]
+    fn unit_hashed_phone() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HashedPhone, "(415) 555-2671", [], {"country_code" => "1"}).unwrap(),
+            liquid_core::value!("cb6880e416769253645cb9c6b8989154bf66a56a77fc14c81fb1019663cbb928")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HashedPhone, "555-2671", [], {}).unwrap(),
+            liquid_core::value!("")
+        );
+    }
+}
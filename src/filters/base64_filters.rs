@@ -1,14 +1,137 @@
 #![allow(clippy::invisible_characters)]
 
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
 use liquid_core::Result;
 use liquid_core::Runtime;
-use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
 use liquid_core::{Value, ValueView};
 
 use base64::prelude::*;
-use base64::engine::general_purpose;
+use base64::alphabet;
+use base64::engine::{general_purpose, DecodePaddingMode, GeneralPurpose};
 
-use super::filter_error;
+use super::{filter_error, invalid_argument};
+
+// Decodes with or without padding. The url safe alphabet is mapped onto the standard one before decoding.
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    general_purpose::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+fn encode(input: &str, url_safe: bool, pad: bool) -> String {
+    let engine = match (url_safe, pad) {
+        (false, true) => &general_purpose::STANDARD,
+        (false, false) => &general_purpose::STANDARD_NO_PAD,
+        (true, true) => &general_purpose::URL_SAFE,
+        (true, false) => &general_purpose::URL_SAFE_NO_PAD,
+    };
+    engine.encode(input)
+}
+
+// Line feeds are added to every 60 encoded characters, like ruby's Base64.encode64
+fn encode_wrapped(input: &str, pad: bool) -> String {
+    let b64 = encode(input, false, pad);
+    let wrapped = textwrap::wrap(b64.as_str(), 60);
+    format!("{}\n", wrapped.join("\n"))
+}
+
+pub(crate) fn decode(input: &str) -> Result<Vec<u8>> {
+    let normalized: String = input
+        .chars()
+        .filter(|x| !x.is_ascii_whitespace())
+        .map(|x| match x {
+            '-' => '+',
+            '_' => '/',
+            _ => x,
+        })
+        .collect();
+    LENIENT.decode(normalized).map_err(|err| {
+        filter_error(format!("Base64 decode error: {}", err))
+    })
+}
+
+fn sextet(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+// Same as ruby's Base64.decode64 (String#unpack1("m")), which never fails.
+// Characters outside the standard alphabet, including newlines and the url safe - and _, are skipped.
+// Decoding stops at the first = that follows at least two characters of a group.
+// A trailing group of a single character is dropped.
+fn decode64(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() / 4 * 3 + 2);
+    let mut group = [0u8; 4];
+    let mut len = 0;
+
+    for &c in input {
+        if c == b'=' && len >= 2 {
+            break;
+        }
+        if let Some(value) = sextet(c) {
+            group[len] = value;
+            len += 1;
+            if len == 4 {
+                output.push(group[0] << 2 | group[1] >> 4);
+                output.push(group[1] << 4 | group[2] >> 2);
+                output.push(group[2] << 6 | group[3]);
+                len = 0;
+            }
+        }
+    }
+
+    if len >= 2 {
+        output.push(group[0] << 2 | group[1] >> 4);
+    }
+    if len == 3 {
+        output.push(group[1] << 4 | group[2] >> 2);
+    }
+    output
+}
+
+// Same as ruby's Base64.strict_decode64 (String#unpack1("m0")).
+// The input must be canonically padded, contain only the standard alphabet and have no unused bits set,
+// which is exactly what the STANDARD engine enforces.
+fn strict_decode64(input: &str) -> Result<Vec<u8>> {
+    general_purpose::STANDARD.decode(input).map_err(|err| {
+        filter_error(format!("Base64 decode error: invalid base64. {}", err))
+    })
+}
+
+// Converts decoded bytes to the filter output according to the utf8 policy.
+// lossy replaces invalid sequences, strict errors on them and hex returns the raw bytes hex encoded.
+pub(crate) fn decoded_output(bytes: Vec<u8>, utf8: Option<&str>) -> Result<String> {
+    match utf8.unwrap_or_default() {
+        "" | "lossy" => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        "strict" => String::from_utf8(bytes).map_err(|err| {
+            filter_error(format!("Decoded data is not utf8. error: {}", err))
+        }),
+        "hex" => Ok(hex::encode(bytes)),
+        other => Err(invalid_argument(
+            "utf8".to_owned(),
+            format!("Unsupported utf8 policy {}", other),
+        )),
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct Base64DecodeArgs {
+    #[parameter(description = "How to treat decoded bytes that are not utf8. lossy, strict or hex. Default is lossy", arg_type = "str", mode = "keyword")]
+    utf8: Option<Expression>,
+}
+
+#[derive(Debug, FilterParameters)]
+struct Base64EncodeArgs {
+    #[parameter(description = "Pads the output with =. Default is true", arg_type = "bool", mode = "keyword")]
+    pad: Option<Expression>,
+}
 
 /// base64_encode
 /// Returns the Base64-encoded version of a string.
@@ -21,23 +144,26 @@ use super::filter_error;
 #[filter(
     name = "base64_encode",
     description = "Returns the Base64-encoded version of a string.",
+    parameters(Base64EncodeArgs),
     parsed(Base64EncodeFilter)
 )]
 pub struct Base64Encode;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "base64_encode"]
-struct Base64EncodeFilter;
+struct Base64EncodeFilter {
+    #[parameters]
+    args: Base64EncodeArgs,
+}
 
 impl Filter for Base64EncodeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
         if s.is_empty() {
             return Ok(Value::scalar(""));
         }
-        let b64 = general_purpose::STANDARD.encode(s.as_str());
-        let wrapped = textwrap::wrap(b64.as_str(), 60);
-        Ok(Value::scalar(format!("{}\n", wrapped.join("\n"))))
+        Ok(Value::scalar(encode_wrapped(s.as_str(), args.pad.unwrap_or(true))))
     }
 }
 
@@ -51,18 +177,23 @@ impl Filter for Base64EncodeFilter {
 #[filter(
     name = "base64_strict_encode",
     description = "Returns the Base64-encoded version of a string with no line feeds added.",
+    parameters(Base64EncodeArgs),
     parsed(Base64StrictEncodeFilter)
 )]
 pub struct Base64StrictEncode;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "base64_strict_encode"]
-struct Base64StrictEncodeFilter;
+struct Base64StrictEncodeFilter {
+    #[parameters]
+    args: Base64EncodeArgs,
+}
 
 impl Filter for Base64StrictEncodeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
-        let b64 = general_purpose::STANDARD.encode(s.as_str());
+        let b64 = encode(s.as_str(), false, args.pad.unwrap_or(true));
         Ok(Value::scalar(b64))
     }
 }
@@ -71,83 +202,126 @@ impl Filter for Base64StrictEncodeFilter {
 #[filter(
     name = "b64_enc",
     description = "Returns the Base64-encoded version of a string.",
+    parameters(Base64EncodeArgs),
     parsed(B64EncFilter)
 )]
 pub struct B64Enc;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "b64_enc"]
-struct B64EncFilter;
+struct B64EncFilter {
+    #[parameters]
+    args: Base64EncodeArgs,
+}
 
 impl Filter for B64EncFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
         if s.is_empty() {
             return Ok(Value::scalar("".to_owned()));
         }
-        let b64 = general_purpose::STANDARD.encode(s.as_str());
-        let wrapped = textwrap::wrap(b64.as_str(), 60);
-        Ok(Value::scalar(format!("{}\n", wrapped.join("\n"))))
+        Ok(Value::scalar(encode_wrapped(s.as_str(), args.pad.unwrap_or(true))))
+    }
+}
+
+/// base64url_encode
+/// Returns the url safe Base64-encoded version of a string, with no line feeds added.
+/// Example:
+/// {{ "subjects?_d" | base64url_encode: pad: false }}
+/// Returns:
+/// c3ViamVjdHM_X2Q
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base64url_encode",
+    description = "Returns the url safe Base64-encoded version of a string.",
+    parameters(Base64EncodeArgs),
+    parsed(Base64UrlEncodeFilter)
+)]
+pub struct Base64UrlEncode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "base64url_encode"]
+struct Base64UrlEncodeFilter {
+    #[parameters]
+    args: Base64EncodeArgs,
+}
+
+impl Filter for Base64UrlEncodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let b64 = encode(s.as_str(), true, args.pad.unwrap_or(true));
+        Ok(Value::scalar(b64))
     }
 }
 
 
 /// base64_decode
-/// Returns the Base64-decoded version of a string.
+/// Returns the Base64-decoded version of a string, like ruby's Base64.decode64.
+/// Line feeds and other characters outside the Base64 alphabet are skipped and decoding stops at padding.
 /// Example:
-/// {{ "lorem ipsum" | base64_decode }}
+/// {{ "bG9yZW0gaXBzdW0=" | base64_decode }}
 /// Returns:
-/// bG9yZW0gaXBzdW0=
-/// For longer strings, line feeds are added to every 60 decoded characters.
+/// lorem ipsum
 #[derive(Clone, ParseFilter, FilterReflection)]
 #[filter(
     name = "base64_decode",
     description = "Returns the Base64-decoded version of a string.",
+    parameters(Base64DecodeArgs),
     parsed(Base64DecodeFilter)
 )]
 pub struct Base64Decode;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "base64_decode"]
-struct Base64DecodeFilter;
+struct Base64DecodeFilter {
+    #[parameters]
+    args: Base64DecodeArgs,
+}
 
 impl Filter for Base64DecodeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
-        let b64 = general_purpose::STANDARD.decode(s.as_str()).map_err(|err| {
-            filter_error(format!("Base64 decode error: {}", err))
-        })?;
-        let decoded = String::from_utf8_lossy(&b64);
-        Ok(Value::scalar(decoded.to_string()))
+        let b64 = decode64(s.as_bytes());
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(b64, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
     }
 }
 
 /// base64_strict_decode
-/// Returns the Base64-decoded version of a string, with no line feeds added.
+/// Returns the Base64-decoded version of a string, like ruby's Base64.strict_decode64.
+/// Errors on line feeds, characters outside the Base64 alphabet and missing or misplaced padding.
 /// Example:
-/// {{ "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec molestie gravida" | base64_strict_decode}
+/// {{ "TG9yZW0gaXBzdW0gZG9sb3Igc2l0IGFtZXQsIGNvbnNlY3RldHVyIGFkaXBpc2NpbmcgZWxpdC4gRG9uZWMgbW9sZXN0aWUgZ3JhdmlkYQ==" | base64_strict_decode }}
 /// Returns:
-/// TG9yZW0gaXBzdW0gZG9sb3Igc2l0IGFtZXQsIGNvbnNlY3RldHVyIGFkaXBpc2NpbmcgZWxpdC4KICAgIERvbmVjIG1vbGVzdGllIGdyYXZpZGE=
+/// Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec molestie gravida
 #[derive(Clone, ParseFilter, FilterReflection)]
 #[filter(
     name = "base64_strict_decode",
-    description = "Returns the Base64-decoded version of a string with no line feeds added.",
+    description = "Returns the Base64-decoded version of a string. Errors on invalid input.",
+    parameters(Base64DecodeArgs),
     parsed(Base64StrictDecodeFilter)
 )]
 pub struct Base64StrictDecode;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "base64_strict_decode"]
-struct Base64StrictDecodeFilter;
+struct Base64StrictDecodeFilter {
+    #[parameters]
+    args: Base64DecodeArgs,
+}
 
 impl Filter for Base64StrictDecodeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
-        let b64 = general_purpose::STANDARD.decode(s.as_str()).map_err(|err| {
-            filter_error(format!("Base64 decode error: {}", err))
-        })?;
-        let decoded = String::from_utf8_lossy(&b64);
-        Ok(Value::scalar(decoded.to_string()))
+        let b64 = strict_decode64(s.as_str())?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(b64, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
     }
 }
 
@@ -155,26 +329,59 @@ impl Filter for Base64StrictDecodeFilter {
 #[filter(
     name = "b64_dec",
     description = "Returns the Base64-decoded version of a string.",
+    parameters(Base64DecodeArgs),
     parsed(B64DecFilter)
 )]
 pub struct B64dec;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "b64_dec"]
-struct B64DecFilter;
+struct B64DecFilter {
+    #[parameters]
+    args: Base64DecodeArgs,
+}
 
 impl Filter for B64DecFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
         if s.is_empty() {
             return Ok(Value::scalar("".to_owned()));
         }
 
-        let b64 = general_purpose::STANDARD.decode(s.as_str()).map_err(|err| {
-            filter_error(format!("Base64 decode error: {}", err))
-        })?;
-        let decoded = String::from_utf8_lossy(&b64);
-        Ok(Value::scalar(decoded.to_string()))
+        let b64 = decode(s.as_str())?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(b64, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+/// base64url_decode
+/// Returns the decoded version of an url safe Base64 string. Padding is optional.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base64url_decode",
+    description = "Returns the decoded version of an url safe Base64 string.",
+    parameters(Base64DecodeArgs),
+    parsed(Base64UrlDecodeFilter)
+)]
+pub struct Base64UrlDecode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "base64url_decode"]
+struct Base64UrlDecodeFilter {
+    #[parameters]
+    args: Base64DecodeArgs,
+}
+
+impl Filter for Base64UrlDecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let b64 = decode(s.as_str())?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(b64, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
     }
 }
 
@@ -213,5 +420,169 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unit_base64_pad() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64Encode, "lorem ipsum", [], {"pad" => false}).unwrap(),
+            liquid_core::value!("bG9yZW0gaXBzdW0\n")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64StrictEncode, "lorem ipsum", [], {"pad" => false}).unwrap(),
+            liquid_core::value!("bG9yZW0gaXBzdW0")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, B64Enc, "lorem ipsum", [], {"pad" => true}).unwrap(),
+            liquid_core::value!("bG9yZW0gaXBzdW0=\n")
+        );
+    }
+
+    #[test]
+    fn unit_base64url_encode() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            liquid_core::call_filter!(Base64UrlEncode, "subjects?_d").unwrap(),
+            liquid_core::value!("c3ViamVjdHM_X2Q=")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64UrlEncode, "subjects?_d", [], {"pad" => false}).unwrap(),
+            liquid_core::value!("c3ViamVjdHM_X2Q")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Base64UrlEncode, "").unwrap(),
+            liquid_core::value!("")
+        );
+    }
+
+    #[test]
+    fn unit_base64_decode() {
+        for encoded in ["c3ViamVjdHM_X2Q=", "c3ViamVjdHM_X2Q", "c3ViamVjdHM/X2Q=", "c3ViamVjdHM/X2Q"] {
+            assert_eq!(
+                liquid_core::call_filter!(Base64UrlDecode, encoded).unwrap(),
+                liquid_core::value!("subjects?_d")
+            );
+            assert_eq!(
+                liquid_core::call_filter!(B64dec, encoded).unwrap(),
+                liquid_core::value!("subjects?_d")
+            );
+        }
+
+        // base64_encode output round trips
+        let s = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec molestie gravida";
+        let encoded = liquid_core::call_filter!(Base64Encode, s).unwrap();
+        assert_eq!(
+            liquid_core::call_filter!(Base64Decode, encoded).unwrap(),
+            liquid_core::value!(s)
+        );
+
+        assert!(liquid_core::call_filter!(B64dec, "c3ViamVjdHM*X2Q").is_err());
+    }
+
+    // Expected values are the results of ruby's Base64.decode64 and Base64.strict_decode64
+    #[test]
+    fn unit_base64_decode_ruby_parity() {
+        let lenient = [
+            ("", ""),
+            ("aGk", "hi"),
+            ("aGk=", "hi"),
+            ("aGk=\n", "hi"),
+            ("aGl=", "hi"),
+            ("aGVs\nbG8=", "hello"),
+            ("aGVs\r\nbG8=\n", "hello"),
+            (" a G V s b G 8 ", "hello"),
+            ("aG!!Vs#bG8", "hello"),
+            ("aGVsbG8-_", "hello"),
+            ("aGVsbG8=d29ybGQ=", "hello"),
+            ("aGVsbG8=\nd29ybGQ=\n", "hello"),
+            ("=aGk", "hi"),
+            ("aG=k", "h"),
+            ("aGk==", "hi"),
+            ("a", ""),
+            ("====", ""),
+        ];
+        for (encoded, decoded) in lenient {
+            assert_eq!(
+                liquid_core::call_filter!(Base64Decode, encoded).unwrap(),
+                liquid_core::value!(decoded),
+                "decode64({:?})",
+                encoded
+            );
+        }
+
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        // The url safe characters are skipped, not translated
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64Decode, "c3ViamVjdHM_X2Q", [], {"utf8" => "hex"}).unwrap(),
+            liquid_core::value!("7375626a6563747317d9")
+        );
+
+        let strict = [
+            ("", ""),
+            ("aGk=", "hi"),
+            ("aA==", "h"),
+            ("bG9yZW0gaXBzdW0=", "lorem ipsum"),
+            ("TG9yZW0gaXBzdW0gZG9sb3Igc2l0IGFtZXQsIGNvbnNlY3RldHVyIGFkaXBpc2NpbmcgZWxpdC4gRG9uZWMgbW9sZXN0aWUgZ3JhdmlkYQ==", "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec molestie gravida"),
+        ];
+        for (encoded, decoded) in strict {
+            assert_eq!(
+                liquid_core::call_filter!(Base64StrictDecode, encoded).unwrap(),
+                liquid_core::value!(decoded),
+                "strict_decode64({:?})",
+                encoded
+            );
+        }
+
+        let invalid = [
+            "aGk",
+            "aGk=\n",
+            "aGk==",
+            "aGl=",
+            "aB==",
+            "aGk=aGk=",
+            "=aGk",
+            "aG!k",
+            "aGVs\nbG8=",
+            "bG9yZW0gaXBzdW0=\n",
+            "c3ViamVjdHM_X2Q=",
+        ];
+        for encoded in invalid {
+            assert!(
+                liquid_core::call_filter!(Base64StrictDecode, encoded).is_err(),
+                "strict_decode64({:?})",
+                encoded
+            );
+        }
+    }
+
+    #[test]
+    fn unit_base64_decode_utf8_policy() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        // 0x68 0x69 0xff 0xfe
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64Decode, "aGn//g==", [], {}).unwrap(),
+            liquid_core::value!("hi\u{fffd}\u{fffd}")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64Decode, "aGn//g==", [], {"utf8" => "lossy"}).unwrap(),
+            liquid_core::value!("hi\u{fffd}\u{fffd}")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Base64StrictDecode, "aGn//g==", [], {"utf8" => "strict"}).is_err());
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64StrictDecode, "aGk=", [], {"utf8" => "strict"}).unwrap(),
+            liquid_core::value!("hi")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, B64dec, "aGn//g==", [], {"utf8" => "hex"}).unwrap(),
+            liquid_core::value!("6869fffe")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base64UrlDecode, "aGn__g", [], {"utf8" => "hex"}).unwrap(),
+            liquid_core::value!("6869fffe")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Base64Decode, "aGk=", [], {"utf8" => "latin1"}).is_err());
+    }
 
 }
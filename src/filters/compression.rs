@@ -0,0 +1,231 @@
+#![allow(clippy::invisible_characters)]
+
+use std::io::{Read, Write};
+
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
+use liquid_core::{Value, ValueView};
+
+use base64::prelude::*;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+
+use super::base64_filters::{decode, decoded_output};
+use super::filter_error;
+
+// Payloads come from links, so inflating is capped to keep a crafted payload from exhausting memory.
+const MAX_INFLATED_LEN: u64 = 1024 * 1024;
+
+fn gzip(input: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(input).and_then(|_| encoder.finish()).map_err(|err| {
+        filter_error(format!("Compression error: {}", err))
+    })
+}
+
+// Raw deflate, without the zlib header and checksum, as every byte counts in a url.
+fn deflate(input: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(input).and_then(|_| encoder.finish()).map_err(|err| {
+        filter_error(format!("Compression error: {}", err))
+    })
+}
+
+fn inflate<R: Read>(decoder: R) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    decoder.take(MAX_INFLATED_LEN + 1).read_to_end(&mut output).map_err(|err| {
+        filter_error(format!("Decompression error: {}", err))
+    })?;
+    if output.len() as u64 > MAX_INFLATED_LEN {
+        return Err(filter_error(format!(
+            "Decompressed data exceeds {} bytes",
+            MAX_INFLATED_LEN
+        )));
+    }
+    Ok(output)
+}
+
+#[derive(Debug, FilterParameters)]
+struct DecompressArgs {
+    #[parameter(description = "How to treat decompressed bytes that are not utf8. lossy, strict or hex. Default is lossy", arg_type = "str", mode = "keyword")]
+    utf8: Option<Expression>,
+}
+
+/// gzip_base64
+/// Gzips a string and returns it Base64-encoded, with no line feeds added.
+/// Pipe through json first to pack a hash or an array.
+/// Example:
+/// {{ cart | json | gzip_base64 }}
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "gzip_base64",
+    description = "Gzips a string and returns it Base64-encoded.",
+    parsed(GzipBase64Filter)
+)]
+pub struct GzipBase64;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "gzip_base64"]
+struct GzipBase64Filter;
+
+impl Filter for GzipBase64Filter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let s = input.to_kstr();
+        if s.is_empty() {
+            return Ok(Value::scalar(""));
+        }
+        let compressed = gzip(s.as_bytes())?;
+        Ok(Value::scalar(BASE64_STANDARD.encode(compressed)))
+    }
+}
+
+/// gunzip_base64
+/// Reverses gzip_base64.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "gunzip_base64",
+    description = "Decodes a Base64 string and gunzips it.",
+    parameters(DecompressArgs),
+    parsed(GunzipBase64Filter)
+)]
+pub struct GunzipBase64;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "gunzip_base64"]
+struct GunzipBase64Filter {
+    #[parameters]
+    args: DecompressArgs,
+}
+
+impl Filter for GunzipBase64Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        if s.is_empty() {
+            return Ok(Value::scalar(""));
+        }
+        let compressed = decode(s.as_str())?;
+        let bytes = inflate(GzDecoder::new(compressed.as_slice()))?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(bytes, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+/// deflate_base64url
+/// Compresses a string with raw deflate and returns it url safe Base64-encoded, without padding.
+/// The output can be used as is in a path segment or a query parameter.
+/// Example:
+/// https://shop.example/recover?cart={{ cart | json | deflate_base64url }}
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "deflate_base64url",
+    description = "Deflates a string and returns it url safe Base64-encoded.",
+    parsed(DeflateBase64UrlFilter)
+)]
+pub struct DeflateBase64Url;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "deflate_base64url"]
+struct DeflateBase64UrlFilter;
+
+impl Filter for DeflateBase64UrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let s = input.to_kstr();
+        if s.is_empty() {
+            return Ok(Value::scalar(""));
+        }
+        let compressed = deflate(s.as_bytes())?;
+        Ok(Value::scalar(BASE64_URL_SAFE_NO_PAD.encode(compressed)))
+    }
+}
+
+/// inflate_base64url
+/// Reverses deflate_base64url.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "inflate_base64url",
+    description = "Decodes a url safe Base64 string and inflates it.",
+    parameters(DecompressArgs),
+    parsed(InflateBase64UrlFilter)
+)]
+pub struct InflateBase64Url;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "inflate_base64url"]
+struct InflateBase64UrlFilter {
+    #[parameters]
+    args: DecompressArgs,
+}
+
+impl Filter for InflateBase64UrlFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        if s.is_empty() {
+            return Ok(Value::scalar(""));
+        }
+        let compressed = decode(s.as_str())?;
+        let bytes = inflate(DeflateDecoder::new(compressed.as_slice()))?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(bytes, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CART: &str = r#"{"items":[{"sku":"SKU-1001","qty":2},{"sku":"SKU-1002","qty":1},{"sku":"SKU-1003","qty":1}],"currency":"USD"}"#;
+
+    #[test]
+    fn unit_gzip_base64() {
+        let encoded = liquid_core::call_filter!(GzipBase64, CART).unwrap();
+        assert_eq!(
+            liquid_core::call_filter!(GunzipBase64, encoded).unwrap(),
+            liquid_core::value!(CART)
+        );
+        // echo -n hello | gzip -n | base64
+        assert_eq!(
+            liquid_core::call_filter!(GunzipBase64, "H4sIAAAAAAAAA8tIzcnJBwCGphA2BQAAAA==").unwrap(),
+            liquid_core::value!("hello")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(GzipBase64, "").unwrap(),
+            liquid_core::value!("")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(GunzipBase64, "").unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(liquid_core::call_filter!(GunzipBase64, "aGVsbG8=").is_err());
+    }
+
+    #[test]
+    fn unit_deflate_base64url() {
+        let encoded = liquid_core::call_filter!(DeflateBase64Url, CART).unwrap();
+        let encoded_str = encoded.to_kstr();
+        assert!(encoded_str.len() < CART.len());
+        assert!(!encoded_str.contains(['+', '/', '=']));
+        assert_eq!(
+            liquid_core::call_filter!(InflateBase64Url, encoded).unwrap(),
+            liquid_core::value!(CART)
+        );
+        assert_eq!(
+            liquid_core::call_filter!(InflateBase64Url, "").unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(liquid_core::call_filter!(InflateBase64Url, "_____w").is_err());
+    }
+
+    #[test]
+    fn unit_inflate_limit() {
+        let bomb = BASE64_URL_SAFE_NO_PAD.encode(deflate(&vec![b'a'; 2 * MAX_INFLATED_LEN as usize]).unwrap());
+        assert!(liquid_core::call_filter!(InflateBase64Url, bomb).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
+use liquid_core::{Value, ValueView};
+
+use crate::currency_config::resolve_currency;
+use crate::utils::{convert_currency, currency_format, decimal_to_string, default_currency_type, parse_decimal};
+use crate::RenderContext;
+
+use super::invalid_input;
+
+#[derive(Debug, FilterParameters)]
+struct ConvertCurrencyArgs {
+    #[parameter(description = "The currency to convert to. USD,EUR,GBP,INR etc", arg_type = "str")]
+    to: Expression,
+
+    #[parameter(description = "The currency of the amount. Default is the currency of the render context", arg_type = "str", mode = "keyword")]
+    from: Option<Expression>,
+}
+
+/// convert_currency
+/// Converts an amount with the exchange rates set on the render context.
+/// The result is an exact decimal, to be formatted with money.
+/// Example:
+/// {{ product.price_usd | convert_currency: "EUR", from: "USD" | money: true, true, "EUR" }}
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "convert_currency",
+    description = "Converts an amount to another currency.",
+    parameters(ConvertCurrencyArgs),
+    parsed(ConvertCurrencyFilter)
+)]
+pub struct ConvertCurrency;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "convert_currency"]
+struct ConvertCurrencyFilter {
+    #[parameters]
+    args: ConvertCurrencyArgs,
+}
+
+impl Filter for ConvertCurrencyFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+
+        let amount = input.to_kstr();
+        if amount.is_empty() {
+            return Ok(Value::scalar(""));
+        }
+        let amount = parse_decimal(amount.as_str())?;
+
+        let from = match args.from {
+            Some(from) if !from.is_empty() => from.to_string(),
+            _ => default_currency_type(&render_context),
+        };
+        let converted = convert_currency(&render_context, &amount, &from, &args.to)?;
+        Ok(Value::scalar(decimal_to_string(&converted, converted.fractional_digit_count())))
+    }
+}
+
+// The upper case code of the input, the currency of the render context when the input is blank.
+fn currency_code(input: &dyn ValueView, render_context: &Option<RenderContext>) -> Result<String> {
+    let code = input.to_kstr().trim().to_ascii_uppercase();
+    let code = if code.is_empty() {
+        default_currency_type(render_context).to_ascii_uppercase()
+    } else {
+        code
+    };
+
+    let overrides = render_context.as_ref().and_then(|cxt| cxt.currency_table.as_deref());
+    match resolve_currency(&code, overrides) {
+        Some(_) => Ok(code),
+        None => Err(invalid_input(format!("Unknown currency {}", code))),
+    }
+}
+
+/// currency_symbol
+/// Returns the symbol of a currency, the currency of the render context when the input is blank.
+/// Example:
+/// {{ "EUR" | currency_symbol }}
+/// Returns:
+/// €
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "currency_symbol",
+    description = "Returns the symbol of a currency.",
+    parsed(CurrencySymbolFilter)
+)]
+pub struct CurrencySymbol;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "currency_symbol"]
+struct CurrencySymbolFilter;
+
+impl Filter for CurrencySymbolFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+        let code = currency_code(input, &render_context)?;
+        Ok(Value::scalar(currency_format(&render_context, &code).symbol))
+    }
+}
+
+/// currency_name
+/// Returns the ISO 4217 name of a currency, the currency of the render context when the input is blank.
+/// Example:
+/// {{ "EUR" | currency_name }}
+/// Returns:
+/// Euro
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "currency_name",
+    description = "Returns the name of a currency.",
+    parsed(CurrencyNameFilter)
+)]
+pub struct CurrencyName;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "currency_name"]
+struct CurrencyNameFilter;
+
+impl Filter for CurrencyNameFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+        let code = currency_code(input, &render_context)?;
+        let overrides = render_context.as_ref().and_then(|cxt| cxt.currency_table.as_deref());
+        // Currencies loaded at runtime may come without a name
+        let name = resolve_currency(&code, overrides)
+            .and_then(|x| x.name)
+            .filter(|x| !x.is_empty())
+            .unwrap_or(code);
+        Ok(Value::scalar(name))
+    }
+}
+
+/// currency_precision
+/// Returns the number of decimals money shows for a currency, the currency of the render context when the input is blank.
+/// Example:
+/// {{ "JPY" | currency_precision }}
+/// Returns:
+/// 0
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "currency_precision",
+    description = "Returns the number of decimals of a currency.",
+    parsed(CurrencyPrecisionFilter)
+)]
+pub struct CurrencyPrecision;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "currency_precision"]
+struct CurrencyPrecisionFilter;
+
+impl Filter for CurrencyPrecisionFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+        let code = currency_code(input, &render_context)?;
+        Ok(Value::scalar(currency_format(&render_context, &code).precision))
+    }
+}
+
+/// currency_symbol_first
+/// Returns whether the symbol of a currency goes before the amount, the currency of the render context when the input is blank.
+/// Example:
+/// {{ "USD" | currency_symbol_first }}
+/// Returns:
+/// true
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "currency_symbol_first",
+    description = "Returns whether the symbol of a currency goes before the amount.",
+    parsed(CurrencySymbolFirstFilter)
+)]
+pub struct CurrencySymbolFirst;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "currency_symbol_first"]
+struct CurrencySymbolFirstFilter;
+
+impl Filter for CurrencySymbolFirstFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+        let code = currency_code(input, &render_context)?;
+        Ok(Value::scalar(currency_format(&render_context, &code).is_symbol_prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::ExchangeRates;
+
+    fn set_rates(runtime: &dyn Runtime) {
+        let mut cxt = runtime.registers().get_mut::<crate::RenderContext>();
+        cxt.set_currency_type("USD".to_owned());
+        let rates = ExchangeRates::from_json(r#"{"base": "USD", "rates": {"EUR": "0.9215", "JPY": "151.37"}}"#).unwrap();
+        cxt.set_exchange_rates(Arc::new(rates));
+    }
+
+    #[test]
+    fn unit_convert_currency() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        set_rates(&runtime);
+
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, ConvertCurrency, "100", "EUR").unwrap(),
+            liquid_core::value!("92.15")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, ConvertCurrency, 19.99, "JPY").unwrap(),
+            liquid_core::value!("3025.8863")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, ConvertCurrency, "92.15", ["USD"], {"from" => "EUR"}).unwrap(),
+            liquid_core::value!("100")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, ConvertCurrency, "", "EUR").unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(crate::call_filter_with_runtime!(runtime, ConvertCurrency, "100", "CHF").is_err());
+        assert!(crate::call_filter_with_runtime!(runtime, ConvertCurrency, "abc", "EUR").is_err());
+
+        let no_rates = liquid_core::runtime::RuntimeBuilder::new().build();
+        assert!(crate::call_filter_with_runtime!(no_rates, ConvertCurrency, "100", "EUR").is_err());
+    }
+
+    #[test]
+    fn unit_money_convert_from() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        set_rates(&runtime);
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, crate::filters::money::Money, "19.99", [false, true, "EUR"], {"convert_from" => "USD"}).unwrap(),
+            liquid_core::value!("18.42")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, crate::filters::money::Money, "19.99", [true, true, "JPY"], {"convert_from" => "USD"}).unwrap(),
+            liquid_core::value!("¥ 3,026")
+        );
+    }
+
+    #[test]
+    fn unit_currency_metadata() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        {
+            let mut cxt = runtime.registers().get_mut::<crate::RenderContext>();
+            cxt.set_currency_type("EUR".to_owned());
+            let tenant = crate::CurrencyTable::from_json(r#"{"currency_types": [{"value": "XYZ", "symbol": "T"}]}"#).unwrap();
+            cxt.set_currency_table(tenant);
+        }
+
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencySymbol, "usd").unwrap(), liquid_core::value!("$"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencySymbol, "").unwrap(), liquid_core::value!("€"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencySymbol, "XYZ").unwrap(), liquid_core::value!("T"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyName, "USD").unwrap(), liquid_core::value!("US Dollar"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyName, "").unwrap(), liquid_core::value!("Euro"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyName, "xyz").unwrap(), liquid_core::value!("XYZ"));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyPrecision, "JPY").unwrap(), liquid_core::value!(0));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyPrecision, "KWD").unwrap(), liquid_core::value!(3));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencyPrecision, "").unwrap(), liquid_core::value!(2));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencySymbolFirst, "USD").unwrap(), liquid_core::value!(true));
+        assert_eq!(crate::call_filter_with_runtime!(runtime, CurrencySymbolFirst, "SEK").unwrap(), liquid_core::value!(false));
+        assert!(crate::call_filter_with_runtime!(runtime, CurrencySymbol, "ABC").is_err());
+
+        let no_context = liquid_core::runtime::RuntimeBuilder::new().build();
+        assert_eq!(crate::call_filter_with_runtime!(no_context, CurrencySymbol, "").unwrap(), liquid_core::value!("$"));
+    }
+}
@@ -0,0 +1,327 @@
+#![allow(clippy::invisible_characters)]
+
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
+use liquid_core::{Value, ValueView};
+
+use data_encoding::Encoding;
+use lazy_static::lazy_static;
+
+use super::base64_filters::decoded_output;
+use super::{filter_error, invalid_argument};
+
+lazy_static! {
+    // https://www.crockford.com/base32.html
+    // Decoding is case insensitive, maps i, l and o to 1 and 0 and ignores hyphens.
+    static ref CROCKFORD: Encoding = {
+        let mut spec = data_encoding::Specification::new();
+        spec.symbols.push_str("0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+        spec.translate.from.push_str("abcdefghjkmnpqrstvwxyzIiLlOo");
+        spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ111100");
+        spec.ignore.push('-');
+        spec.encoding().unwrap()
+    };
+}
+
+fn base32_encoding(variant: Option<&str>) -> Result<Encoding> {
+    match variant.unwrap_or_default() {
+        "" | "rfc4648" => Ok(data_encoding::BASE32),
+        "crockford" => Ok(CROCKFORD.clone()),
+        other => Err(invalid_argument(
+            "variant".to_owned(),
+            format!("Unsupported base32 variant {}", other),
+        )),
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct DecodeArgs {
+    #[parameter(description = "How to treat decoded bytes that are not utf8. lossy, strict or hex. Default is lossy", arg_type = "str", mode = "keyword")]
+    utf8: Option<Expression>,
+}
+
+/// hex_encode
+/// Returns the lowercase hex encoded version of a string.
+/// Example:
+/// {{ "hi" | hex_encode }}
+/// Returns:
+/// 6869
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hex_encode",
+    description = "Returns the hex encoded version of a string.",
+    parsed(HexEncodeFilter)
+)]
+pub struct HexEncode;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "hex_encode"]
+struct HexEncodeFilter;
+
+impl Filter for HexEncodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let s = input.to_kstr();
+        Ok(Value::scalar(data_encoding::HEXLOWER.encode(s.as_bytes())))
+    }
+}
+
+/// hex_decode
+/// Returns the decoded version of a hex string. Upper and lower case digits are accepted.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hex_decode",
+    description = "Returns the decoded version of a hex string.",
+    parameters(DecodeArgs),
+    parsed(HexDecodeFilter)
+)]
+pub struct HexDecode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hex_decode"]
+struct HexDecodeFilter {
+    #[parameters]
+    args: DecodeArgs,
+}
+
+impl Filter for HexDecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let bytes = data_encoding::HEXLOWER_PERMISSIVE.decode(s.as_bytes()).map_err(|err| {
+            filter_error(format!("Hex decode error: {}", err))
+        })?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(bytes, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct Base32EncodeArgs {
+    #[parameter(description = "Base32 alphabet. rfc4648 or crockford. Default is rfc4648", arg_type = "str", mode = "keyword")]
+    variant: Option<Expression>,
+}
+
+/// base32_encode
+/// Returns the Base32-encoded version of a string.
+/// Example:
+/// {{ "foobar" | base32_encode }}
+/// Returns:
+/// MZXW6YTBOI======
+/// {{ "foobar" | base32_encode: variant: "crockford" }}
+/// Returns:
+/// CSQPYRK1E8
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base32_encode",
+    description = "Returns the Base32-encoded version of a string.",
+    parameters(Base32EncodeArgs),
+    parsed(Base32EncodeFilter)
+)]
+pub struct Base32Encode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "base32_encode"]
+struct Base32EncodeFilter {
+    #[parameters]
+    args: Base32EncodeArgs,
+}
+
+impl Filter for Base32EncodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let variant = args.variant.map(|x| x.to_string());
+        let encoding = base32_encoding(variant.as_deref())?;
+        Ok(Value::scalar(encoding.encode(s.as_bytes())))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct Base32DecodeArgs {
+    #[parameter(description = "Base32 alphabet. rfc4648 or crockford. Default is rfc4648", arg_type = "str", mode = "keyword")]
+    variant: Option<Expression>,
+
+    #[parameter(description = "How to treat decoded bytes that are not utf8. lossy, strict or hex. Default is lossy", arg_type = "str", mode = "keyword")]
+    utf8: Option<Expression>,
+}
+
+/// base32_decode
+/// Returns the decoded version of a Base32 string.
+/// rfc4648 input may be lowercase and padding is optional.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base32_decode",
+    description = "Returns the decoded version of a Base32 string.",
+    parameters(Base32DecodeArgs),
+    parsed(Base32DecodeFilter)
+)]
+pub struct Base32Decode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "base32_decode"]
+struct Base32DecodeFilter {
+    #[parameters]
+    args: Base32DecodeArgs,
+}
+
+impl Filter for Base32DecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let variant = args.variant.map(|x| x.to_string());
+        let encoding = base32_encoding(variant.as_deref())?;
+
+        let bytes = if encoding == data_encoding::BASE32 {
+            let normalized = s.as_str().trim_end_matches('=').to_ascii_uppercase();
+            data_encoding::BASE32_NOPAD.decode(normalized.as_bytes())
+        } else {
+            encoding.decode(s.as_bytes())
+        }.map_err(|err| {
+            filter_error(format!("Base32 decode error: {}", err))
+        })?;
+
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(bytes, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+/// base58_encode
+/// Returns the Base58-encoded version of a string, using the bitcoin alphabet.
+/// Example:
+/// {{ "hello world" | base58_encode }}
+/// Returns:
+/// StV1DL6CwTryKyV
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base58_encode",
+    description = "Returns the Base58-encoded version of a string.",
+    parsed(Base58EncodeFilter)
+)]
+pub struct Base58Encode;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "base58_encode"]
+struct Base58EncodeFilter;
+
+impl Filter for Base58EncodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+        let s = input.to_kstr();
+        Ok(Value::scalar(bs58::encode(s.as_bytes()).into_string()))
+    }
+}
+
+/// base58_decode
+/// Returns the decoded version of a Base58 string.
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "base58_decode",
+    description = "Returns the decoded version of a Base58 string.",
+    parameters(DecodeArgs),
+    parsed(Base58DecodeFilter)
+)]
+pub struct Base58Decode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "base58_decode"]
+struct Base58DecodeFilter {
+    #[parameters]
+    args: DecodeArgs,
+}
+
+impl Filter for Base58DecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let bytes = bs58::decode(s.as_str()).into_vec().map_err(|err| {
+            filter_error(format!("Base58 decode error: {}", err))
+        })?;
+        let utf8 = args.utf8.map(|x| x.to_string());
+        let decoded = decoded_output(bytes, utf8.as_deref())?;
+        Ok(Value::scalar(decoded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_hex() {
+        assert_eq!(
+            liquid_core::call_filter!(HexEncode, "hi").unwrap(),
+            liquid_core::value!("6869")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(HexEncode, "").unwrap(),
+            liquid_core::value!("")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(HexDecode, "6869").unwrap(),
+            liquid_core::value!("hi")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(HexDecode, "686A").unwrap(),
+            liquid_core::value!("hj")
+        );
+        assert!(liquid_core::call_filter!(HexDecode, "686").is_err());
+    }
+
+    #[test]
+    fn unit_base32() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            liquid_core::call_filter!(Base32Encode, "foobar").unwrap(),
+            liquid_core::value!("MZXW6YTBOI======")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base32Encode, "foobar", [], {"variant" => "crockford"}).unwrap(),
+            liquid_core::value!("CSQPYRK1E8")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Base32Encode, "").unwrap(),
+            liquid_core::value!("")
+        );
+
+        for encoded in ["MZXW6YTBOI======", "MZXW6YTBOI", "mzxw6ytboi"] {
+            assert_eq!(
+                liquid_core::call_filter!(Base32Decode, encoded).unwrap(),
+                liquid_core::value!("foobar")
+            );
+        }
+        for encoded in ["CSQPYRK1E8", "csqpyrk1e8", "CSQP-YRKIE8"] {
+            assert_eq!(
+                crate::call_filter_with_keywords!(runtime, Base32Decode, encoded, [], {"variant" => "crockford"}).unwrap(),
+                liquid_core::value!("foobar")
+            );
+        }
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Base32Decode, "AAAQF7Y=", [], {"utf8" => "hex"}).unwrap(),
+            liquid_core::value!("000102ff")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Base32Decode, "CSQPYRKUE8", [], {"variant" => "crockford"}).is_err());
+        assert!(crate::call_filter_with_keywords!(runtime, Base32Encode, "foobar", [], {"variant" => "z-base-32"}).is_err());
+    }
+
+    #[test]
+    fn unit_base58() {
+        assert_eq!(
+            liquid_core::call_filter!(Base58Encode, "hello world").unwrap(),
+            liquid_core::value!("StV1DL6CwTryKyV")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Base58Decode, "StV1DL6CwTryKyV").unwrap(),
+            liquid_core::value!("hello world")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Base58Decode, "").unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(liquid_core::call_filter!(Base58Decode, "StV1DL6CwTryKy0").is_err());
+    }
+}
@@ -1,33 +1,127 @@
 #![allow(clippy::invisible_characters)]
 
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
 use liquid_core::Result;
 use liquid_core::Runtime;
-use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
 use liquid_core::{Value, ValueView};
 use md5::Digest;
+use std::borrow::Cow;
+
+use crate::utils::keyring_key;
+use super::{invalid_argument, invalid_input};
+
+fn encode_output(bytes: &[u8], encoding: Option<&str>) -> Result<String> {
+    let encoding = match encoding.unwrap_or_default() {
+        "" | "hex" => &data_encoding::HEXLOWER,
+        "HEX" => &data_encoding::HEXUPPER,
+        "base64" => &data_encoding::BASE64,
+        "base64url" => &data_encoding::BASE64URL,
+        other => {
+            return Err(invalid_argument(
+                "encoding".to_owned(),
+                format!("Unsupported encoding {}", other),
+            ));
+        }
+    };
+    Ok(encoding.encode(bytes))
+}
+
+fn decode_input<'a>(input: &'a str, input_encoding: Option<&str>) -> Result<Cow<'a, [u8]>> {
+    let decoded = match input_encoding.unwrap_or_default() {
+        "" | "utf8" => return Ok(Cow::Borrowed(input.as_bytes())),
+        "hex" => data_encoding::HEXLOWER_PERMISSIVE.decode(input.as_bytes()),
+        "base64" => data_encoding::BASE64.decode(input.as_bytes()),
+        other => {
+            return Err(invalid_argument(
+                "input_encoding".to_owned(),
+                format!("Unsupported input encoding {}", other),
+            ));
+        }
+    };
+    decoded.map(Cow::Owned).map_err(|err| {
+        invalid_input(format!("Input decoding error: {}", err))
+    })
+}
+
+pub(crate) fn sha1(bytes: &[u8]) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, bytes).as_ref().to_vec()
+}
+
+pub(crate) fn sha256(bytes: &[u8]) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA256, bytes).as_ref().to_vec()
+}
+
+pub(crate) fn sha384(bytes: &[u8]) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA384, bytes).as_ref().to_vec()
+}
+
+pub(crate) fn sha512(bytes: &[u8]) -> Vec<u8> {
+    ring::digest::digest(&ring::digest::SHA512, bytes).as_ref().to_vec()
+}
+
+pub(crate) fn sha3_256(bytes: &[u8]) -> Vec<u8> {
+    sha3::Sha3_256::digest(bytes).to_vec()
+}
+
+pub(crate) fn md5(bytes: &[u8]) -> Vec<u8> {
+    md5::Md5::digest(bytes).to_vec()
+}
+
+// crc32 is emitted as its 4 big endian bytes, so hex output is the usual 8 character checksum.
+pub(crate) fn crc32(bytes: &[u8]) -> Vec<u8> {
+    crc32fast::hash(bytes).to_be_bytes().to_vec()
+}
+
+#[derive(Debug, FilterParameters)]
+struct DigestArgs {
+    #[parameter(description = "Output encoding. hex, HEX, base64 or base64url. Default is hex", arg_type = "str", mode = "keyword")]
+    encoding: Option<Expression>,
+
+    #[parameter(description = "Encoding of the input string. utf8, hex or base64. Default is utf8", arg_type = "str", mode = "keyword")]
+    input_encoding: Option<Expression>,
+}
+
+fn digest(
+    input: &dyn ValueView,
+    runtime: &dyn Runtime,
+    args: &DigestArgs,
+    hash: fn(&[u8]) -> Vec<u8>,
+) -> Result<Value> {
+    let s = input.to_kstr();
+    if s.as_str().is_empty() {
+        return Ok(Value::scalar(String::new()));
+    }
+    let args = args.evaluate(runtime)?;
+
+    let input_encoding = args.input_encoding.map(|x| x.to_string());
+    let bytes = decode_input(s.as_str(), input_encoding.as_deref())?;
+    let encoding = args.encoding.map(|x| x.to_string());
+    let encoded = encode_output(&hash(&bytes), encoding.as_deref())?;
+    Ok(Value::scalar(encoded))
+}
 
 
 #[derive(Clone, ParseFilter, FilterReflection)]
 #[filter(
     name = "sha1",
     description = "computes sha-1 of a string",
+    parameters(DigestArgs),
     parsed(Sha1Filter)
 )]
 pub struct Sha1;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "sha1"]
-struct Sha1Filter;
+struct Sha1Filter {
+    #[parameters]
+    args: DigestArgs,
+}
 
 impl Filter for Sha1Filter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
-        let s = input.to_kstr();
-        if s.as_str().is_empty() {
-            return Ok(Value::scalar(String::new()));
-        }
-        let digest = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, s.as_bytes());
-        let encoded = data_encoding::HEXLOWER.encode(digest.as_ref());
-        Ok(Value::scalar(encoded))
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, sha1)
     }
 }
 
@@ -35,23 +129,89 @@ impl Filter for Sha1Filter {
 #[filter(
     name = "sha256",
     description = "computes sha-256 of a string",
+    parameters(DigestArgs),
     parsed(Sha256Filter)
 )]
 pub struct Sha256;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "sha256"]
-struct Sha256Filter;
+struct Sha256Filter {
+    #[parameters]
+    args: DigestArgs,
+}
 
 impl Filter for Sha256Filter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
-        let s = input.to_kstr();
-        if s.as_str().is_empty() {
-            return Ok(Value::scalar(String::new()));
-        }
-        let digest = ring::digest::digest(&ring::digest::SHA256, s.as_bytes());
-        let encoded = data_encoding::HEXLOWER.encode(digest.as_ref());
-        Ok(Value::scalar(encoded))
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, sha256)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sha384",
+    description = "computes sha-384 of a string",
+    parameters(DigestArgs),
+    parsed(Sha384Filter)
+)]
+pub struct Sha384;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sha384"]
+struct Sha384Filter {
+    #[parameters]
+    args: DigestArgs,
+}
+
+impl Filter for Sha384Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, sha384)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sha512",
+    description = "computes sha-512 of a string",
+    parameters(DigestArgs),
+    parsed(Sha512Filter)
+)]
+pub struct Sha512;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sha512"]
+struct Sha512Filter {
+    #[parameters]
+    args: DigestArgs,
+}
+
+impl Filter for Sha512Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, sha512)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "sha3_256",
+    description = "computes sha3-256 of a string",
+    parameters(DigestArgs),
+    parsed(Sha3_256Filter)
+)]
+#[allow(non_camel_case_types)]
+pub struct Sha3_256;
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "sha3_256"]
+struct Sha3_256Filter {
+    #[parameters]
+    args: DigestArgs,
+}
+
+impl Filter for Sha3_256Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, sha3_256)
     }
 }
 
@@ -60,25 +220,156 @@ impl Filter for Sha256Filter {
 #[filter(
     name = "md5",
     description = "computes md5 of a string",
+    parameters(DigestArgs),
     parsed(Md5Filter)
 )]
 pub struct Md5;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "md5"]
-struct Md5Filter;
+struct Md5Filter {
+    #[parameters]
+    args: DigestArgs,
+}
 
 impl Filter for Md5Filter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
-        let s = input.to_kstr();
-        if s.as_str().is_empty() {
-            return Ok(Value::scalar(String::new()));
-        }
-        let mut hasher = md5::Md5::new();
-        hasher.update(s.as_bytes());
-        let result = hasher.finalize();
-        let encoded = data_encoding::HEXLOWER.encode(result.as_ref());
-        Ok(Value::scalar(encoded))
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, md5)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "crc32",
+    description = "computes crc32 checksum of a string",
+    parameters(DigestArgs),
+    parsed(Crc32Filter)
+)]
+pub struct Crc32;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "crc32"]
+struct Crc32Filter {
+    #[parameters]
+    args: DigestArgs,
+}
+
+impl Filter for Crc32Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        digest(input, runtime, &self.args, crc32)
+    }
+}
+
+// A keyring id takes precedence over a literal key.
+pub(crate) fn resolve_key(runtime: &dyn Runtime, key: Option<&str>, key_id: Option<&str>) -> Result<Vec<u8>> {
+    if let Some(key_id) = key_id {
+        let render_context = runtime
+            .registers()
+            .get::<crate::RenderContext>();
+        return keyring_key(&render_context, key_id);
+    }
+    key.map(|x| x.as_bytes().to_vec())
+        .ok_or_else(|| invalid_argument("key", "required argument key is missing"))
+}
+
+#[derive(Debug, FilterParameters)]
+struct HmacArgs {
+    #[parameter(description = "Secret key.", arg_type = "str")]
+    key: Option<Expression>,
+
+    #[parameter(description = "Id of a key in the render context keyring. Used instead of key.", arg_type = "str", mode = "keyword")]
+    key_id: Option<Expression>,
+
+    #[parameter(description = "Output encoding. hex, base64 or base64url. Default is hex", arg_type = "str", mode = "keyword")]
+    encoding: Option<Expression>,
+}
+
+fn hmac(
+    algorithm: ring::hmac::Algorithm,
+    input: &dyn ValueView,
+    runtime: &dyn Runtime,
+    args: &HmacArgs,
+) -> Result<Value> {
+    let s = input.to_kstr();
+    if s.as_str().is_empty() {
+        return Ok(Value::scalar(String::new()));
+    }
+    let args = args.evaluate(runtime)?;
+
+    let key = args.key.map(|x| x.to_string());
+    let key_id = args.key_id.map(|x| x.to_string());
+    let key = resolve_key(runtime, key.as_deref(), key_id.as_deref())?;
+
+    let key = ring::hmac::Key::new(algorithm, &key);
+    let tag = ring::hmac::sign(&key, s.as_bytes());
+    let encoding = args.encoding.map(|x| x.to_string());
+    let encoded = encode_output(tag.as_ref(), encoding.as_deref())?;
+    Ok(Value::scalar(encoded))
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hmac_sha1",
+    description = "computes hmac-sha1 of a string with the given key",
+    parameters(HmacArgs),
+    parsed(HmacSha1Filter)
+)]
+pub struct HmacSha1;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hmac_sha1"]
+struct HmacSha1Filter {
+    #[parameters]
+    args: HmacArgs,
+}
+
+impl Filter for HmacSha1Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        hmac(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, input, runtime, &self.args)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hmac_sha256",
+    description = "computes hmac-sha256 of a string with the given key",
+    parameters(HmacArgs),
+    parsed(HmacSha256Filter)
+)]
+pub struct HmacSha256;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hmac_sha256"]
+struct HmacSha256Filter {
+    #[parameters]
+    args: HmacArgs,
+}
+
+impl Filter for HmacSha256Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        hmac(ring::hmac::HMAC_SHA256, input, runtime, &self.args)
+    }
+}
+
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "hmac_sha512",
+    description = "computes hmac-sha512 of a string with the given key",
+    parameters(HmacArgs),
+    parsed(HmacSha512Filter)
+)]
+pub struct HmacSha512;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "hmac_sha512"]
+struct HmacSha512Filter {
+    #[parameters]
+    args: HmacArgs,
+}
+
+impl Filter for HmacSha512Filter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        hmac(ring::hmac::HMAC_SHA512, input, runtime, &self.args)
     }
 }
 
@@ -105,4 +396,97 @@ mod tests {
             liquid_core::value!("e169b640b9ceda26ce4c3d8a919eb42c")
         );
     }
+
+    #[test]
+    fn unit_digests() {
+        assert_eq!(
+            liquid_core::call_filter!(Sha1, "testuser@getblueshift.com").unwrap(),
+            liquid_core::value!("8fcb4174b195670a9689d22da5ddb8d363c8f45a")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Sha384, "testuser@getblueshift.com").unwrap(),
+            liquid_core::value!("fe953173a6a1d19e44b5429bac30970355d08280102883d75286faa358e020b8f543b87c11c65bd3940c95007f75cce7")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Sha512, "testuser@getblueshift.com").unwrap(),
+            liquid_core::value!("53809ce21b8453102ad78835f5359e256d0626f657a5f37febd524437a08dc185e36dc69587fbc777f1421dde7f7474422aabdd85dbdb4f58480349ac04f246b")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Sha3_256, "testuser@getblueshift.com").unwrap(),
+            liquid_core::value!("f31d08c75804b65141fa8e26fd8404f12065b6b546b1c6a65477d2010a0e9042")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(Crc32, "123456789").unwrap(),
+            liquid_core::value!("cbf43926")
+        );
+    }
+
+    #[test]
+    fn unit_digest_encodings() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Sha256, "Hello World", [], {"encoding" => "HEX"}).unwrap(),
+            liquid_core::value!("A591A6D40BF420404A011733CFB7B190D62C65BF0BCDA32B57B277D9AD9F146E")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Sha256, "Hello World", [], {"encoding" => "base64"}).unwrap(),
+            liquid_core::value!("pZGm1Av0IEBKARczz7exkNYsZb8LzaMrV7J32a2fFG4=")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Sha256, "hello", [], {"encoding" => "base64"}).unwrap(),
+            liquid_core::value!("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Sha256, "hello", [], {"encoding" => "base64url"}).unwrap(),
+            liquid_core::value!("LPJNul-wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=")
+        );
+        // "Hello World" as hex and base64 hashes the same bytes
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Sha256, "48656c6c6f20576f726c64", [], {"input_encoding" => "hex"}).unwrap(),
+            liquid_core::value!("a591a6d40bf420404a011733cfb7b190d62c65bf0bcda32b57b277d9ad9f146e")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Md5, "SGVsbG8gV29ybGQ=", [], {"input_encoding" => "base64"}).unwrap(),
+            liquid_core::value!("b10a8db164e0754105b7a99be72e3fe5")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Sha256, "zz", [], {"input_encoding" => "hex"}).is_err());
+        assert!(crate::call_filter_with_keywords!(runtime, Sha256, "Hello World", [], {"encoding" => "base32"}).is_err());
+    }
+
+    #[test]
+    fn unit_hmac() {
+        assert_eq!(
+            liquid_core::call_filter!(HmacSha1, "testuser@getblueshift.com", "secret").unwrap(),
+            liquid_core::value!("50adad1c2eec163ce303790b9a513fb5d1c6daab")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(HmacSha256, "testuser@getblueshift.com", "secret").unwrap(),
+            liquid_core::value!("451d9506c6a66ab26901ef851f2eaa12035590c3e4468c55d8e176abf86e72e8")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(HmacSha512, "testuser@getblueshift.com", "secret").unwrap(),
+            liquid_core::value!("bb3e2a99bfb55a91b2163e5895110e97d813d995ed9b001ede9a8640b2cd96241f80b5c994747fa9b81339887468e6ea4be645a9b1e77f2feedab65361205a2e")
+        );
+        assert!(liquid_core::call_filter!(HmacSha256, "testuser@getblueshift.com").is_err());
+    }
+
+    #[test]
+    fn unit_hmac_keyring() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        {
+            let mut cxt = runtime.registers().get_mut::<crate::RenderContext>();
+            cxt.add_key("webhook".to_owned(), b"secret".to_vec());
+        }
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HmacSha256, "testuser@getblueshift.com", [], {"key_id" => "webhook", "encoding" => "base64"}).unwrap(),
+            liquid_core::value!("RR2VBsamarJpAe+FHy6qEgNVkMPkRoxV2OF2q/hucug=")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, HmacSha256, "testuser@getblueshift.com", [], {"key_id" => "webhook", "encoding" => "base64url"}).unwrap(),
+            liquid_core::value!("RR2VBsamarJpAe-FHy6qEgNVkMPkRoxV2OF2q_hucug=")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, HmacSha256, "testuser@getblueshift.com", [], {"key_id" => "missing"}).is_err());
+    }
 }
@@ -1,13 +1,19 @@
 pub mod string;
 pub mod hashing;
+pub mod jwt;
 pub mod money;
+pub mod currency;
 pub mod base64_filters;
+pub mod encoding_filters;
+pub mod compression;
 pub mod aes;
 pub mod timezone;
 pub mod to_json;
 pub mod number;
 pub mod url_encode;
+pub mod url_filters;
 pub mod array;
+pub mod audience;
 
 use liquid_core::Error;
 
@@ -7,7 +7,7 @@ use liquid_core::Runtime;
 use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
 use liquid_core::{Value, ValueView};
 
-use crate::utils::format_currency;
+use crate::utils::{format_currency, MoneyFormat, Rounding, SignFormat, TrailingZeros};
 
 #[derive(Debug, FilterParameters)]
 struct MoneyArgs {
@@ -19,6 +19,21 @@ struct MoneyArgs {
 
     #[parameter(description = "Currency type. USD,EUR,GBP,INR etc", arg_type = "str")]
     currency_type: Option<Expression>,
+
+    #[parameter(description = "Rounding to the minor units of the currency. half_up, half_even, half_down, truncate, up, ceiling or floor. Default is half_up", arg_type = "str", mode = "keyword")]
+    round_mode: Option<Expression>,
+
+    #[parameter(description = "Currency of the amount, converted with the exchange rates of the render context.", arg_type = "str", mode = "keyword")]
+    convert_from: Option<Expression>,
+
+    #[parameter(description = "Format of negative amounts, %u is the symbol and %n the number. \"(%u%n)\" gives ($10.00). Default is -%u%n", arg_type = "str", mode = "keyword")]
+    negative_format: Option<Expression>,
+
+    #[parameter(description = "Put a + before positive amounts. Default is false", arg_type = "bool", mode = "keyword")]
+    positive_sign: Option<Expression>,
+
+    #[parameter(description = "Show the currency code after the amount in place of the symbol, 10.00 USD. Default is false", arg_type = "bool", mode = "keyword")]
+    show_code: Option<Expression>,
 }
 
 #[derive(Clone, ParseFilter, FilterReflection)]
@@ -44,7 +59,19 @@ impl Filter for MoneyFilter {
             .registers()
             .get::<crate::RenderContext>();
 
-        let formatted_as_money = format_currency(&render_context, input, args.use_symbol, args.use_space, args.currency_type)?;
+        let options = MoneyFormat {
+            use_symbol: args.use_symbol,
+            use_space: args.use_space,
+            rounding: Rounding::parse(args.round_mode.as_deref())?,
+            convert_from: args.convert_from.map(|x| x.to_string()),
+            sign: SignFormat {
+                negative_format: args.negative_format.map(|x| x.to_string()),
+                positive_sign: args.positive_sign.unwrap_or(false),
+            },
+            show_code: args.show_code.unwrap_or(false),
+            ..Default::default()
+        };
+        let formatted_as_money = format_currency(&render_context, input, args.currency_type, &options)?;
         Ok(Value::scalar(formatted_as_money))
     }
 }
@@ -59,6 +86,24 @@ struct MoneyWithoutTrailingZerosArgs {
 
     #[parameter(description = "Currency type. USD,EUR,GBP,INR etc", arg_type = "str")]
     currency_type: Option<Expression>,
+
+    #[parameter(description = "Rounding to the minor units of the currency. half_up, half_even, half_down, truncate, up, ceiling or floor. Default is half_up", arg_type = "str", mode = "keyword")]
+    round_mode: Option<Expression>,
+
+    #[parameter(description = "Also strip the zeros ending the fractional part, 10.50 gives 10.5. Default is false", arg_type = "bool", mode = "keyword")]
+    strip_insignificant_zeros: Option<Expression>,
+
+    #[parameter(description = "Currency of the amount, converted with the exchange rates of the render context.", arg_type = "str", mode = "keyword")]
+    convert_from: Option<Expression>,
+
+    #[parameter(description = "Format of negative amounts, %u is the symbol and %n the number. \"(%u%n)\" gives ($10.00). Default is -%u%n", arg_type = "str", mode = "keyword")]
+    negative_format: Option<Expression>,
+
+    #[parameter(description = "Put a + before positive amounts. Default is false", arg_type = "bool", mode = "keyword")]
+    positive_sign: Option<Expression>,
+
+    #[parameter(description = "Show the currency code after the amount in place of the symbol, 10.00 USD. Default is false", arg_type = "bool", mode = "keyword")]
+    show_code: Option<Expression>,
 }
 
 #[derive(Clone, ParseFilter, FilterReflection)]
@@ -84,8 +129,25 @@ impl Filter for MoneyWithoutTrailingZerosFilter {
             .registers()
             .get::<crate::RenderContext>();
 
-        let formatted_as_money = format_currency(&render_context, input, args.use_symbol, args.use_space, args.currency_type)?;
-        Ok(Value::scalar(formatted_as_money.replace(".00", "")))
+        let trailing_zeros = if args.strip_insignificant_zeros.unwrap_or(false) {
+            TrailingZeros::StripInsignificant
+        } else {
+            TrailingZeros::StripWhole
+        };
+        let options = MoneyFormat {
+            use_symbol: args.use_symbol,
+            use_space: args.use_space,
+            rounding: Rounding::parse(args.round_mode.as_deref())?,
+            trailing_zeros,
+            convert_from: args.convert_from.map(|x| x.to_string()),
+            sign: SignFormat {
+                negative_format: args.negative_format.map(|x| x.to_string()),
+                positive_sign: args.positive_sign.unwrap_or(false),
+            },
+            show_code: args.show_code.unwrap_or(false),
+        };
+        let formatted_as_money = format_currency(&render_context, input, args.currency_type, &options)?;
+        Ok(Value::scalar(formatted_as_money))
     }
 }
 
@@ -142,4 +204,174 @@ mod tests {
 
     }
 
+    #[test]
+    fn unit_money_precision() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 1234.4, false, true, "JPY").unwrap(),
+            liquid_core::value!("1,234")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 1000, false, true, "KRW").unwrap(),
+            liquid_core::value!("1,000")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 10.1234, false, true, "KWD").unwrap(),
+            liquid_core::value!("10.123")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 10, false, true, "BHD").unwrap(),
+            liquid_core::value!("10.000")
+        );
+    }
+
+    #[test]
+    fn unit_money_grouping() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 1000000, true, true, "INR").unwrap(),
+            liquid_core::value!("₹ 10,00,000.00")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, -123456789.5, true, false, "INR").unwrap(),
+            liquid_core::value!("-₹12,34,56,789.50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, 1000000, false, true, "INR").unwrap(),
+            liquid_core::value!("10,00,000.00")
+        );
+    }
+
+    #[test]
+    fn unit_money_exact() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "90071992547409.93", false, true, "USD").unwrap(),
+            liquid_core::value!("90,071,992,547,409.93")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "1.005", false, true, "USD").unwrap(),
+            liquid_core::value!("1.01")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "1.005", [false, true, "USD"], {"round_mode" => "half_even"}).unwrap(),
+            liquid_core::value!("1.00")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "10.999", [false, true, "USD"], {"round_mode" => "truncate"}).unwrap(),
+            liquid_core::value!("10.99")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "(1,234.5)", false, true, "USD").unwrap(),
+            liquid_core::value!("-1,234.50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "1e3", false, true, "USD").unwrap(),
+            liquid_core::value!("1,000.00")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "1.234,50", false, true, "USD").unwrap(),
+            liquid_core::value!("1.234,50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "$1,234.5", false, true, "USD").unwrap(),
+            liquid_core::value!("1,234.50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "-$ 1 234.5 USD", false, true, "USD").unwrap(),
+            liquid_core::value!("-1,234.50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, Money, "12a34", false, true, "USD").unwrap(),
+            liquid_core::value!("12a34")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Money, "1", [], {"round_mode" => "nearest"}).is_err());
+    }
+
+    #[test]
+    fn unit_money_without_trailing_zeros() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, MoneyWithoutTrailingZeros, "10", true, true, "EUR").unwrap(),
+            liquid_core::value!("€ 10")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, MoneyWithoutTrailingZeros, "1000.5", true, true, "EUR").unwrap(),
+            liquid_core::value!("€ 1.000,50")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, MoneyWithoutTrailingZeros, "1000", true, true, "SEK").unwrap(),
+            liquid_core::value!("1 000 kr")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, MoneyWithoutTrailingZeros, "1000.001", false, true, "USD").unwrap(),
+            liquid_core::value!("1,000")
+        );
+        assert_eq!(
+            crate::call_filter_with_runtime!(runtime, MoneyWithoutTrailingZeros, "10.003", false, true, "KWD").unwrap(),
+            liquid_core::value!("10.003")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, MoneyWithoutTrailingZeros, "10.5", [true, true, "EUR"], {"strip_insignificant_zeros" => true}).unwrap(),
+            liquid_core::value!("€ 10,5")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, MoneyWithoutTrailingZeros, "1000.00", [false, true, "USD"], {"strip_insignificant_zeros" => true}).unwrap(),
+            liquid_core::value!("1,000")
+        );
+    }
+
+    #[test]
+    fn unit_money_sign_format() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        let money = |amount: &str, currency: &str, use_space: bool, negative_format: &str| {
+            crate::call_filter_with_keywords!(runtime, Money, amount, [true, use_space, currency], {"negative_format" => negative_format}).unwrap()
+        };
+        assert_eq!(money("-10", "USD", false, "-%u%n"), liquid_core::value!("-$10.00"));
+        assert_eq!(money("-10", "USD", false, "%u-%n"), liquid_core::value!("$-10.00"));
+        assert_eq!(money("-10", "USD", false, "(%u%n)"), liquid_core::value!("($10.00)"));
+        assert_eq!(money("-10", "USD", false, "%n- %u"), liquid_core::value!("10.00- $"));
+        assert_eq!(money("-1234.5", "EUR", true, "(%u %n)"), liquid_core::value!("(€ 1.234,50)"));
+        assert_eq!(money("10", "USD", false, "(%u%n)"), liquid_core::value!("$10.00"));
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "-10", [false, true, "USD"], {"negative_format" => "(%u%n)"}).unwrap(),
+            liquid_core::value!("(10.00)")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, MoneyWithoutTrailingZeros, "-1000", [true, true, "SEK"], {"negative_format" => "%n- %u"}).unwrap(),
+            liquid_core::value!("1 000- kr")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, Money, "-10", [], {"negative_format" => "(%u)"}).is_err());
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "10", [true, false, "USD"], {"positive_sign" => true}).unwrap(),
+            liquid_core::value!("+$10.00")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "-0.001", [true, false, "USD"], {"positive_sign" => true}).unwrap(),
+            liquid_core::value!("$0.00")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "-10", [true, false, "USD"], {"positive_sign" => true}).unwrap(),
+            liquid_core::value!("-$10.00")
+        );
+
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "10", [true, true, "usd"], {"show_code" => true}).unwrap(),
+            liquid_core::value!("10.00 USD")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, Money, "-1234.5", [true, true, "EUR"], {"show_code" => true, "negative_format" => "(%n %u)"}).unwrap(),
+            liquid_core::value!("(1.234,50 EUR)")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, MoneyWithoutTrailingZeros, "1000", [true, true, "JPY"], {"show_code" => true, "positive_sign" => true}).unwrap(),
+            liquid_core::value!("+1,000 JPY")
+        );
+    }
 }
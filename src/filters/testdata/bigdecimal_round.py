@@ -0,0 +1,66 @@
+# Port of bigdecimal_round.rb to Python's decimal module: same inputs, same rounding, same output.
+# It stands in where Ruby is not available, the table it writes does not show Ruby parity.
+#   python3 src/filters/testdata/bigdecimal_round.py > src/filters/testdata/bigdecimal_round.tsv
+import decimal
+import sys
+from decimal import Decimal, getcontext, ROUND_HALF_UP, ROUND_HALF_EVEN, ROUND_HALF_DOWN, ROUND_DOWN, ROUND_UP, ROUND_CEILING, ROUND_FLOOR
+getcontext().prec = 200
+MODES = [("half_up", ROUND_HALF_UP), ("half_even", ROUND_HALF_EVEN), ("half_down", ROUND_HALF_DOWN),
+         ("truncate", ROUND_DOWN), ("up", ROUND_UP), ("ceiling", ROUND_CEILING), ("floor", ROUND_FLOOR)]
+INPUTS_PER_MODE = 200
+EDGE_CASES = """0 -0 0.5 -0.5 1.5 -1.5 2.5 -2.5 0.05 -0.05 0.005 -0.005 0.0005 -0.0005
+  0.045 -0.045 1.005 -1.005 0.125 -0.125 9.995 -9.995 99.95 -99.95 999.9995
+  0.001 -0.001 0.0001 1e-7 -1e-7 2.5e3 123.456 -123.456 31.825 1231.825 111.2346""".split()
+seed = 1
+def rand_below(n):
+    global seed
+    seed = (seed * 6364136223846793005 + 1442695040888963407) % 2**64
+    return (seed >> 33) % n
+def digits(count):
+    return "".join(str(rand_below(10)) for _ in range(count))
+def rounded_digits(count):
+    c = rand_below(3)
+    if c == 0: return digits(count) + "5"
+    if c == 1:
+        a = digits(count) + "5"
+        return a + digits(1 + rand_below(3))
+    return digits(rand_below(9))
+def sign():
+    return "" if rand_below(2) == 0 else "-"
+def decimal_input(precision):
+    integer = str(rand_below(10**rand_below(8)))
+    fraction = rounded_digits(precision)
+    s = sign()
+    return s + integer if fraction == "" else s + integer + "." + fraction
+def significant_input(precision):
+    first = str(1 + rand_below(9))
+    mantissa = first + rounded_digits(precision - 1)
+    point = rand_below(len(mantissa) + 7) - 3
+    if point <= 0: number = "0." + "0" * -point + mantissa
+    elif point >= len(mantissa): number = mantissa + "0" * (point - len(mantissa))
+    else: number = mantissa[:point] + "." + mantissa[point:]
+    return sign() + number
+def to_s_f(d):
+    if d.is_zero():
+        return "-0.0" if d.is_signed() else "0.0"
+    s = format(d.normalize(), "f")
+    return s if "." in s else s + ".0"
+def rnd(inp, precision, significant, mode):
+    d = Decimal(inp)
+    if significant:
+        exponent = 0 if d.is_zero() else d.adjusted() + 1
+        precision -= exponent
+    return to_s_f(d.quantize(Decimal(1).scaleb(-precision), rounding=mode))
+print("# python %s, decimal %s, not ruby: port of bigdecimal_round.rb" % (sys.version.split()[0], decimal.__version__))
+print('# mode\tsignificant\tprecision\tinput\tBigDecimal(input).round(...).to_s("F"), see bigdecimal_round.rb')
+for name, mode in MODES:
+    rows = []
+    for inp in EDGE_CASES:
+        a = rand_below(4); rows.append((inp, a, False))
+        b = 1 + rand_below(4); rows.append((inp, b, True))
+    for _ in range(INPUTS_PER_MODE):
+        p = rand_below(7); rows.append((decimal_input(p), p, False))
+    for _ in range(INPUTS_PER_MODE):
+        p = 1 + rand_below(6); rows.append((significant_input(p), p, True))
+    for inp, p, sig in rows:
+        print("\t".join([name, "true" if sig else "false", str(p), inp, rnd(inp, p, sig, mode)]))
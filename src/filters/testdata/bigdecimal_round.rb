@@ -0,0 +1,81 @@
+# Expected results of Ruby's BigDecimal rounding, which number_with_precision is checked against.
+#   ruby src/filters/testdata/bigdecimal_round.rb > src/filters/testdata/bigdecimal_round.tsv
+require "bigdecimal"
+
+MODES = %w[half_up half_even half_down truncate up ceiling floor]
+INPUTS_PER_MODE = 200
+
+EDGE_CASES = %w[
+  0 -0 0.5 -0.5 1.5 -1.5 2.5 -2.5 0.05 -0.05 0.005 -0.005 0.0005 -0.0005
+  0.045 -0.045 1.005 -1.005 0.125 -0.125 9.995 -9.995 99.95 -99.95 999.9995
+  0.001 -0.001 0.0001 1e-7 -1e-7 2.5e3 123.456 -123.456 31.825 1231.825 111.2346
+]
+
+# A linear congruential generator, so the inputs are the same wherever this runs
+$seed = 1
+def rand_below(n)
+  $seed = ($seed * 6364136223846793005 + 1442695040888963407) % 2**64
+  ($seed >> 33) % n
+end
+
+def digits(count)
+  Array.new(count) { rand_below(10) }.join
+end
+
+# Digits ending in a tie, in a near tie, or random
+def rounded_digits(count)
+  case rand_below(3)
+  when 0 then digits(count) + "5"
+  when 1 then digits(count) + "5" + digits(1 + rand_below(3))
+  else digits(rand_below(9))
+  end
+end
+
+def sign
+  rand_below(2) == 0 ? "" : "-"
+end
+
+# Rounded at the precision-th fractional digit
+def decimal_input(precision)
+  integer = rand_below(10**rand_below(8)).to_s
+  fraction = rounded_digits(precision)
+  fraction.empty? ? "#{sign}#{integer}" : "#{sign}#{integer}.#{fraction}"
+end
+
+# Rounded at the precision-th significant digit, with the point anywhere around them
+def significant_input(precision)
+  mantissa = (1 + rand_below(9)).to_s + rounded_digits(precision - 1)
+  point = rand_below(mantissa.length + 7) - 3
+  number = if point <= 0
+             "0." + "0" * -point + mantissa
+           elsif point >= mantissa.length
+             mantissa + "0" * (point - mantissa.length)
+           else
+             mantissa[0...point] + "." + mantissa[point..]
+           end
+  sign + number
+end
+
+def round(input, precision, significant, mode)
+  number = BigDecimal(input)
+  # exponent is the number of digits before the point: 0.00123 is 0.123e-2
+  precision -= number.exponent if significant
+  number.round(precision, mode.to_sym).to_s("F")
+end
+
+puts "# ruby #{RUBY_VERSION}, bigdecimal #{BigDecimal::VERSION}"
+puts "# mode\tsignificant\tprecision\tinput\tBigDecimal(input).round(...).to_s(\"F\"), see bigdecimal_round.rb"
+MODES.each do |mode|
+  rows = EDGE_CASES.flat_map { |input| [[input, rand_below(4), false], [input, 1 + rand_below(4), true]] }
+  INPUTS_PER_MODE.times do
+    precision = rand_below(7)
+    rows << [decimal_input(precision), precision, false]
+  end
+  INPUTS_PER_MODE.times do
+    precision = 1 + rand_below(6)
+    rows << [significant_input(precision), precision, true]
+  end
+  rows.each do |input, precision, significant|
+    puts [mode, significant, precision, input, round(input, precision, significant, mode)].join("\t")
+  end
+end
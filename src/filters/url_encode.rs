@@ -1,20 +1,36 @@
 #![allow(clippy::invisible_characters)]
 
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
 use liquid_core::Result;
 use liquid_core::Runtime;
-use liquid_core::{Display_filter, Filter, FilterReflection, ParseFilter};
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
 use liquid_core::{Value, ValueView};
 
+use percent_encoding::{percent_decode, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use phf::phf_map;
-use phf::phf_set;
 
+use super::{filter_error, invalid_argument};
 
-static ALLOWED_CHARS: phf::Set<char> = phf_set! {
-    'A','B','C','D','E','F','G','H','I','J','K','L','M','N','O','P','Q','R','S','T','U','V','W','X','Y','Z',
-    'a','b','c','d','e','f','g','h','i','j','k','l','m','n','o','p','q','r','s','t','u','v','w','x','y','z',
-    '1','2','3','4','5','6','7','8','9','0',
-    '_','.','-','~',
-};
+// Everything but the RFC 3986 unreserved characters, which are also the ones ruby's CGI.escape leaves alone.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+// RFC 3986 userinfo, less the : that separates the user name from the password.
+const USERINFO: &AsciiSet = &UNRESERVED
+    .remove(b'!')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')')
+    .remove(b'*')
+    .remove(b'+')
+    .remove(b',')
+    .remove(b';')
+    .remove(b'=');
+
+// RFC 3986 path characters. / is kept, so a whole path can be encoded at once.
+const PATH: &AsciiSet = &USERINFO.remove(b':').remove(b'@').remove(b'/');
 
 
 static PERCENT_ENCODING_MAP: phf::Map<char, &'static str> = phf_map! {
@@ -41,39 +57,177 @@ static PERCENT_ENCODING_MAP: phf::Map<char, &'static str> = phf_map! {
 };
 
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    // application/x-www-form-urlencoded, space is +
+    Form,
+    // space is %20
+    Rfc3986,
+    Path,
+    Userinfo,
+}
+
+fn mode(mode: Option<&str>) -> Result<Mode> {
+    match mode.unwrap_or_default() {
+        "" | "form" => Ok(Mode::Form),
+        "rfc3986" => Ok(Mode::Rfc3986),
+        "path" => Ok(Mode::Path),
+        "userinfo" => Ok(Mode::Userinfo),
+        other => Err(invalid_argument(
+            "mode".to_owned(),
+            format!("Unsupported mode {}", other),
+        )),
+    }
+}
+
+fn encode(s: &str, mode: Mode) -> String {
+    match mode {
+        // A literal + is encoded, so any %20 comes from a space
+        Mode::Form => utf8_percent_encode(s, UNRESERVED).to_string().replace("%20", "+"),
+        Mode::Rfc3986 => utf8_percent_encode(s, UNRESERVED).to_string(),
+        Mode::Path => utf8_percent_encode(s, PATH).to_string(),
+        Mode::Userinfo => utf8_percent_encode(s, USERINFO).to_string(),
+    }
+}
+
+// With strict, malformed % sequences and decoded bytes that are not utf8 are errors.
+// Otherwise malformed sequences are kept as is and invalid utf8 is replaced.
+fn decode(s: &str, mode: Mode, strict: bool) -> Result<String> {
+    let bytes = s.as_bytes();
+    if strict {
+        for (index, _) in s.match_indices('%') {
+            let valid = bytes.len() > index + 2
+                && bytes[index + 1].is_ascii_hexdigit()
+                && bytes[index + 2].is_ascii_hexdigit();
+            if !valid {
+                return Err(filter_error(format!(
+                    "Invalid percent encoding at position {}",
+                    index
+                )));
+            }
+        }
+    }
+
+    let plus_decoded;
+    let bytes = if mode == Mode::Form && s.contains('+') {
+        plus_decoded = s.replace('+', " ");
+        plus_decoded.as_bytes()
+    } else {
+        bytes
+    };
+
+    let decoded: Vec<u8> = percent_decode(bytes).collect();
+    if strict {
+        String::from_utf8(decoded).map_err(|err| {
+            filter_error(format!("Decoded data is not utf8. error: {}", err))
+        })
+    } else {
+        Ok(String::from_utf8_lossy(&decoded).into_owned())
+    }
+}
+
+// Same as ruby's CGI.escape
+pub(crate) fn url_encode(s: &str) -> String {
+    encode(s, Mode::Form)
+}
+
+// Spaces are %20 in a path, + only means space in a query string.
+pub(crate) fn url_encode_path_segment(s: &str) -> String {
+    encode(s, Mode::Rfc3986)
+}
+
+
+#[derive(Debug, FilterParameters)]
+struct UrlEncodeArgs {
+    #[parameter(description = "Encoding profile. form, rfc3986, path or userinfo. Default is form", arg_type = "str", mode = "keyword")]
+    mode: Option<Expression>,
+}
+
+/// url_encode
+/// Percent encodes a string. The mode selects what is left unencoded.
+/// form: letters, digits and -._~ with space as +, like ruby's CGI.escape.
+/// rfc3986: as form, with space as %20.
+/// path: also leaves / : @ and the RFC 3986 sub-delims unencoded.
+/// userinfo: also leaves the RFC 3986 sub-delims unencoded, for a user name or a password.
+/// Example:
+/// {{ "a b/c" | url_encode: mode: "path" }}
+/// Returns:
+/// a%20b/c
 #[derive(Clone, ParseFilter, FilterReflection)]
 #[filter(
     name = "url_encode",
     description = "url_encode a string.",
+    parameters(UrlEncodeArgs),
     parsed(UrlEncodeFilter)
 )]
 pub struct UrlEncode;
 
-#[derive(Debug, Default, Display_filter)]
+#[derive(Debug, FromFilterParameters, Display_filter)]
 #[name = "url_encode"]
-struct UrlEncodeFilter;
+struct UrlEncodeFilter {
+    #[parameters]
+    args: UrlEncodeArgs,
+}
 
 impl Filter for UrlEncodeFilter {
-    fn evaluate(&self, input: &dyn ValueView, _runtime: &dyn Runtime) -> Result<Value> {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
         let s = input.to_kstr();
-        let mut chars = String::with_capacity(s.len());
-        for ch in s.as_str().chars() {
-            if ALLOWED_CHARS.contains(&ch) {
-                chars.push(ch);
-            } else if let Some(tr) = PERCENT_ENCODING_MAP.get(&ch) {
-                for tr_ch in tr.chars() {
-                    chars.push(tr_ch);
-                }
-            } else {
-                let mut b = [0; 4];
-                ch.encode_utf8(&mut b);
-                for i in &b[..ch.len_utf8()] {
-                    chars.push_str(&format!("%{:02X}", i));
-                }
-            }
-        }
+        let mode_name = args.mode.map(|x| x.to_string());
+        let mode = mode(mode_name.as_deref())?;
+        Ok(Value::scalar(encode(s.as_str(), mode)))
+    }
+}
 
-        Ok(Value::scalar(chars))
+#[derive(Debug, FilterParameters)]
+struct UrlDecodeArgs {
+    #[parameter(description = "Encoding profile. In form mode, the default, + is decoded to a space", arg_type = "str", mode = "keyword")]
+    mode: Option<Expression>,
+
+    #[parameter(description = "How to treat malformed % sequences and non utf8 output. keep or error. Default is keep", arg_type = "str", mode = "keyword")]
+    invalid: Option<Expression>,
+}
+
+/// url_decode
+/// Decodes a percent encoded string.
+/// Malformed % sequences are kept as is, unless invalid: "error" is given.
+/// Example:
+/// {{ "spring+sale%21" | url_decode }}
+/// Returns:
+/// spring sale!
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_decode",
+    description = "Decodes a percent encoded string.",
+    parameters(UrlDecodeArgs),
+    parsed(UrlDecodeFilter)
+)]
+pub struct UrlDecode;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "url_decode"]
+struct UrlDecodeFilter {
+    #[parameters]
+    args: UrlDecodeArgs,
+}
+
+impl Filter for UrlDecodeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let mode_name = args.mode.map(|x| x.to_string());
+        let mode = mode(mode_name.as_deref())?;
+        let strict = match args.invalid.as_deref() {
+            None | Some("keep") => false,
+            Some("error") => true,
+            Some(other) => {
+                return Err(invalid_argument(
+                    "invalid".to_owned(),
+                    format!("Unsupported policy {}", other),
+                ))
+            }
+        };
+        Ok(Value::scalar(decode(s.as_str(), mode, strict)?))
     }
 }
 
@@ -111,3 +265,74 @@ impl Filter for EscapeUrlFilter {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_url_encode() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+        let s = "a b+c/d?e=f&g:h@i~j*k(l)é";
+
+        assert_eq!(
+            liquid_core::call_filter!(UrlEncode, s).unwrap(),
+            liquid_core::value!("a+b%2Bc%2Fd%3Fe%3Df%26g%3Ah%40i~j%2Ak%28l%29%C3%A9")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => "form"}).unwrap(),
+            liquid_core::value!("a+b%2Bc%2Fd%3Fe%3Df%26g%3Ah%40i~j%2Ak%28l%29%C3%A9")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => "rfc3986"}).unwrap(),
+            liquid_core::value!("a%20b%2Bc%2Fd%3Fe%3Df%26g%3Ah%40i~j%2Ak%28l%29%C3%A9")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => "path"}).unwrap(),
+            liquid_core::value!("a%20b+c/d%3Fe=f&g:h@i~j*k(l)%C3%A9")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => "userinfo"}).unwrap(),
+            liquid_core::value!("a%20b+c%2Fd%3Fe=f&g%3Ah%40i~j*k(l)%C3%A9")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => "html"}).is_err());
+    }
+
+    #[test]
+    fn unit_url_decode() {
+        let runtime = liquid_core::runtime::RuntimeBuilder::new().build();
+
+        assert_eq!(
+            liquid_core::call_filter!(UrlDecode, "spring+sale%21%20%C3%A9").unwrap(),
+            liquid_core::value!("spring sale! é")
+        );
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlDecode, "a+b%2Bc", [], {"mode" => "rfc3986"}).unwrap(),
+            liquid_core::value!("a+b+c")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlDecode, "100%+off%2").unwrap(),
+            liquid_core::value!("100% off%2")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlDecode, "%FFok").unwrap(),
+            liquid_core::value!("\u{fffd}ok")
+        );
+        assert!(crate::call_filter_with_keywords!(runtime, UrlDecode, "100%+off", [], {"invalid" => "error"}).is_err());
+        assert!(crate::call_filter_with_keywords!(runtime, UrlDecode, "off%2", [], {"invalid" => "error"}).is_err());
+        assert!(crate::call_filter_with_keywords!(runtime, UrlDecode, "%FFok", [], {"invalid" => "error"}).is_err());
+        assert_eq!(
+            crate::call_filter_with_keywords!(runtime, UrlDecode, "100%25+off", [], {"invalid" => "error"}).unwrap(),
+            liquid_core::value!("100% off")
+        );
+
+        let s = "a b+c/d?e=f&g:h@i~j*k(l)é";
+        for mode in ["form", "rfc3986", "path", "userinfo"] {
+            let encoded = crate::call_filter_with_keywords!(runtime, UrlEncode, s, [], {"mode" => mode}).unwrap();
+            assert_eq!(
+                crate::call_filter_with_keywords!(runtime, UrlDecode, encoded, [], {"mode" => mode}).unwrap(),
+                liquid_core::value!(s)
+            );
+        }
+    }
+}
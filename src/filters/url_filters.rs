@@ -0,0 +1,680 @@
+#![allow(clippy::invisible_characters)]
+
+use liquid_core::Expression;
+use liquid_core::FilterParameters;
+use liquid_core::Result;
+use liquid_core::Runtime;
+use liquid_core::runtime::RenderingMode;
+use liquid_core::{Display_filter, Filter, FilterReflection, FromFilterParameters, ParseFilter};
+use liquid_core::{Object, ObjectView, Value, ValueView};
+
+use url::Url;
+
+use super::invalid_input;
+use super::url_encode::{url_encode, url_encode_path_segment};
+
+// Invalid and relative urls are an error in strict mode.
+// In lax mode they give None, which the filters render as an empty string.
+fn parse(input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Option<Url>> {
+    let s = input.to_kstr();
+    match Url::parse(s.as_str().trim()) {
+        Ok(url) => Ok(Some(url)),
+        Err(_) if matches!(runtime.render_mode(), RenderingMode::Lax) => Ok(None),
+        Err(e) => Err(invalid_input(format!("Invalid url: {}", e))),
+    }
+}
+
+/// url_host
+/// Returns the host of an url, lowercased.
+/// Example:
+/// {{ "https://Shop.Example.com/p/123?utm_source=email" | url_host }}
+/// Returns:
+/// shop.example.com
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_host",
+    description = "Returns the host of an url.",
+    parsed(UrlHostFilter)
+)]
+pub struct UrlHost;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_host"]
+struct UrlHostFilter;
+
+impl Filter for UrlHostFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let host = parse(input, runtime)?
+            .and_then(|url| url.host_str().map(|x| x.to_owned()))
+            .unwrap_or_default();
+        Ok(Value::scalar(host))
+    }
+}
+
+/// url_path
+/// Returns the path of an url, still percent encoded.
+/// Example:
+/// {{ "https://shop.example.com/p/123?utm_source=email" | url_path }}
+/// Returns:
+/// /p/123
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_path",
+    description = "Returns the path of an url.",
+    parsed(UrlPathFilter)
+)]
+pub struct UrlPath;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_path"]
+struct UrlPathFilter;
+
+impl Filter for UrlPathFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let path = parse(input, runtime)?.map(|url| url.path().to_owned()).unwrap_or_default();
+        Ok(Value::scalar(path))
+    }
+}
+
+/// url_scheme
+/// Returns the scheme of an url, lowercased.
+/// Example:
+/// {{ "HTTPS://shop.example.com" | url_scheme }}
+/// Returns:
+/// https
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_scheme",
+    description = "Returns the scheme of an url.",
+    parsed(UrlSchemeFilter)
+)]
+pub struct UrlScheme;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_scheme"]
+struct UrlSchemeFilter;
+
+impl Filter for UrlSchemeFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let scheme = parse(input, runtime)?.map(|url| url.scheme().to_owned()).unwrap_or_default();
+        Ok(Value::scalar(scheme))
+    }
+}
+
+/// url_fragment
+/// Returns the fragment of an url, without the #.
+/// Example:
+/// {{ "https://shop.example.com/faq#returns" | url_fragment }}
+/// Returns:
+/// returns
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_fragment",
+    description = "Returns the fragment of an url.",
+    parsed(UrlFragmentFilter)
+)]
+pub struct UrlFragment;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_fragment"]
+struct UrlFragmentFilter;
+
+impl Filter for UrlFragmentFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let fragment = parse(input, runtime)?
+            .and_then(|url| url.fragment().map(|x| x.to_owned()))
+            .unwrap_or_default();
+        Ok(Value::scalar(fragment))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct UrlQueryParamArgs {
+    #[parameter(description = "Name of the query parameter.", arg_type = "str")]
+    name: Expression,
+}
+
+/// url_query_param
+/// Returns the decoded value of a query parameter. The first value is returned when the parameter is repeated.
+/// Example:
+/// {{ "https://shop.example.com/p/123?utm_source=spring+sale&id=7" | url_query_param: "utm_source" }}
+/// Returns:
+/// spring sale
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_query_param",
+    description = "Returns the value of a query parameter of an url.",
+    parameters(UrlQueryParamArgs),
+    parsed(UrlQueryParamFilter)
+)]
+pub struct UrlQueryParam;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "url_query_param"]
+struct UrlQueryParamFilter {
+    #[parameters]
+    args: UrlQueryParamArgs,
+}
+
+impl Filter for UrlQueryParamFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+
+        let value = parse(input, runtime)?
+            .and_then(|url| {
+                url.query_pairs()
+                    .find(|(name, _)| name == args.name.as_str())
+                    .map(|(_, value)| value.into_owned())
+            })
+            .unwrap_or_default();
+        Ok(Value::scalar(value))
+    }
+}
+
+/// url_query_params
+/// Returns the decoded query parameters of an url as a hash.
+/// A repeated parameter gives an array of its values.
+/// Example:
+/// {% assign params = "https://shop.example.com/?utm_source=email&tag=a&tag=b" | url_query_params %}
+/// {{ params.utm_source }} {{ params.tag | join: "," }}
+/// Returns:
+/// email a,b
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_query_params",
+    description = "Returns the query parameters of an url as a hash.",
+    parsed(UrlQueryParamsFilter)
+)]
+pub struct UrlQueryParams;
+
+#[derive(Debug, Default, Display_filter)]
+#[name = "url_query_params"]
+struct UrlQueryParamsFilter;
+
+impl Filter for UrlQueryParamsFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let mut params = Object::new();
+        if let Some(url) = parse(input, runtime)? {
+            for (name, value) in url.query_pairs() {
+                let value = Value::scalar(value.into_owned());
+                match params.get_mut(name.as_ref()) {
+                    Some(Value::Array(values)) => values.push(value),
+                    Some(existing) => {
+                        let first = std::mem::replace(existing, Value::Nil);
+                        *existing = Value::array(vec![first, value]);
+                    }
+                    None => {
+                        params.insert(name.into_owned().into(), value);
+                    }
+                }
+            }
+        }
+        Ok(Value::Object(params))
+    }
+}
+
+// Splits an url into the part before the query, the query and the fragment.
+// Works on the raw string, so relative urls are supported and existing encoding is kept as is.
+fn split_url(url: &str) -> (&str, Option<&str>, Option<&str>) {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    match rest.split_once('?') {
+        Some((base, query)) => (base, Some(query), fragment),
+        None => (rest, None, fragment),
+    }
+}
+
+fn join_url(base: &str, pairs: &[String], fragment: Option<&str>) -> String {
+    let mut url = base.to_owned();
+    if !pairs.is_empty() {
+        url.push('?');
+        url.push_str(&pairs.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    url
+}
+
+fn query_pairs(query: Option<&str>) -> Vec<String> {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_owned())
+        .collect()
+}
+
+// Matches name itself and its nested rails style keys, name[] and name[key].
+fn is_param(pair: &str, name: &str) -> bool {
+    url::form_urlencoded::parse(pair.as_bytes())
+        .next()
+        .map(|(key, _)| {
+            key == name || key.strip_prefix(name).is_some_and(|x| x.starts_with('['))
+        })
+        .unwrap_or(false)
+}
+
+// Encodes a value like rails' to_query.
+// Hashes become key[nested]=value and arrays key[]=value. Empty hashes and arrays are skipped.
+// As in rails, the encoded keys of a hash are sorted, unless the hash is inside an array,
+// and array elements keep their order.
+// Objects do not keep insertion order, so the keys of a hash inside an array are taken in key order.
+fn to_query_pairs(prefix: &str, value: &dyn ValueView, pairs: &mut Vec<String>) {
+    if let Some(object) = value.as_object() {
+        let mut entries: Vec<_> = object.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut groups = Vec::new();
+        for (key, value) in entries {
+            let key = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}[{}]", prefix, key)
+            };
+            let mut group = Vec::new();
+            to_query_pairs(&key, value, &mut group);
+            if !group.is_empty() {
+                groups.push(group.join("&"));
+            }
+        }
+        if !prefix.contains("[]") {
+            groups.sort();
+        }
+        pairs.extend(groups);
+    } else if let Some(array) = value.as_array() {
+        let key = format!("{}[]", prefix);
+        for value in array.values() {
+            to_query_pairs(&key, value, pairs);
+        }
+    } else {
+        let value = if value.is_nil() {
+            String::new()
+        } else {
+            value.to_kstr().to_string()
+        };
+        pairs.push(format!("{}={}", url_encode(prefix), url_encode(&value)));
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct UrlSetParamArgs {
+    #[parameter(description = "Name of the query parameter.", arg_type = "str")]
+    name: Expression,
+
+    #[parameter(description = "Value of the query parameter. Hashes and arrays are encoded like to_query.", arg_type = "any")]
+    value: Expression,
+}
+
+/// url_set_param
+/// Sets a query parameter, replacing any existing values of it. The value is url encoded.
+/// Example:
+/// {{ "https://shop.example.com/p/123?utm_source=web#reviews" | url_set_param: "utm_source", "spring sale" }}
+/// Returns:
+/// https://shop.example.com/p/123?utm_source=spring+sale#reviews
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_set_param",
+    description = "Sets a query parameter of an url.",
+    parameters(UrlSetParamArgs),
+    parsed(UrlSetParamFilter)
+)]
+pub struct UrlSetParam;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "url_set_param"]
+struct UrlSetParamFilter {
+    #[parameters]
+    args: UrlSetParamArgs,
+}
+
+impl Filter for UrlSetParamFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+        let name = args.name.as_str();
+
+        let (base, query, fragment) = split_url(s.as_str());
+        let mut pairs = query_pairs(query);
+        let position = pairs.iter().position(|x| is_param(x, name)).unwrap_or(pairs.len());
+        pairs.retain(|x| !is_param(x, name));
+
+        let mut new_pairs = Vec::new();
+        to_query_pairs(name, args.value.as_view(), &mut new_pairs);
+        pairs.splice(position..position, new_pairs);
+
+        Ok(Value::scalar(join_url(base, &pairs, fragment)))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct UrlRemoveParamArgs {
+    #[parameter(description = "Name of the query parameter.", arg_type = "str")]
+    name: Expression,
+}
+
+/// url_remove_param
+/// Removes a query parameter, including its nested name[] and name[key] forms.
+/// Example:
+/// {{ "https://shop.example.com/p/123?token=abc&id=7" | url_remove_param: "token" }}
+/// Returns:
+/// https://shop.example.com/p/123?id=7
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_remove_param",
+    description = "Removes a query parameter from an url.",
+    parameters(UrlRemoveParamArgs),
+    parsed(UrlRemoveParamFilter)
+)]
+pub struct UrlRemoveParam;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "url_remove_param"]
+struct UrlRemoveParamFilter {
+    #[parameters]
+    args: UrlRemoveParamArgs,
+}
+
+impl Filter for UrlRemoveParamFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let (base, query, fragment) = split_url(s.as_str());
+        let mut pairs = query_pairs(query);
+        pairs.retain(|x| !is_param(x, args.name.as_str()));
+
+        Ok(Value::scalar(join_url(base, &pairs, fragment)))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct UrlAppendPathArgs {
+    #[parameter(description = "Path to append. Each / separated segment is url encoded.", arg_type = "str")]
+    path: Expression,
+}
+
+/// url_append_path
+/// Appends path segments to the path of an url, keeping its query and fragment.
+/// Example:
+/// {{ "https://shop.example.com/collections/?ref=email" | url_append_path: "summer sale/shoes" }}
+/// Returns:
+/// https://shop.example.com/collections/summer%20sale/shoes?ref=email
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "url_append_path",
+    description = "Appends segments to the path of an url.",
+    parameters(UrlAppendPathArgs),
+    parsed(UrlAppendPathFilter)
+)]
+pub struct UrlAppendPath;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "url_append_path"]
+struct UrlAppendPathFilter {
+    #[parameters]
+    args: UrlAppendPathArgs,
+}
+
+impl Filter for UrlAppendPathFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        let s = input.to_kstr();
+
+        let (base, query, fragment) = split_url(s.as_str());
+        let mut path = base.trim_end_matches('/').to_owned();
+        for segment in args.path.as_str().split('/').filter(|x| !x.is_empty()) {
+            path.push('/');
+            path.push_str(&url_encode_path_segment(segment));
+        }
+        if args.path.as_str().ends_with('/') {
+            path.push('/');
+        }
+
+        Ok(Value::scalar(join_url(&path, &query_pairs(query), fragment)))
+    }
+}
+
+#[derive(Debug, FilterParameters)]
+struct ToQueryArgs {
+    #[parameter(description = "Wraps the keys, as in namespace[key]=value.", arg_type = "str")]
+    namespace: Option<Expression>,
+}
+
+/// to_query
+/// Converts a hash into an url encoded query string, like rails' to_query.
+/// Nested hashes and arrays use the rails bracket notation. Keys are sorted.
+/// Example:
+/// {{ cart | to_query }}
+/// with cart = {"id": 7, "items": ["a b", "c"], "user": {"name": "Jo"}}
+/// Returns:
+/// id=7&items%5B%5D=a+b&items%5B%5D=c&user%5Bname%5D=Jo
+#[derive(Clone, ParseFilter, FilterReflection)]
+#[filter(
+    name = "to_query",
+    description = "Converts a hash into an url encoded query string.",
+    parameters(ToQueryArgs),
+    parsed(ToQueryFilter)
+)]
+pub struct ToQuery;
+
+#[derive(Debug, FromFilterParameters, Display_filter)]
+#[name = "to_query"]
+struct ToQueryFilter {
+    #[parameters]
+    args: ToQueryArgs,
+}
+
+impl Filter for ToQueryFilter {
+    fn evaluate(&self, input: &dyn ValueView, runtime: &dyn Runtime) -> Result<Value> {
+        let args = self.args.evaluate(runtime)?;
+        if input.is_nil() {
+            return Ok(Value::scalar(""));
+        }
+        if input.as_object().is_none() {
+            return Err(invalid_input("Hash expected"));
+        }
+
+        let mut pairs = Vec::new();
+        let namespace = args.namespace.map(|x| x.to_string()).unwrap_or_default();
+        to_query_pairs(&namespace, input, &mut pairs);
+        Ok(Value::scalar(pairs.join("&")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://Shop.Example.com/p/123%20x?utm_source=spring+sale&id=7&tag=a&tag=b#reviews";
+
+    #[test]
+    fn unit_url_components() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlHost, URL).unwrap(),
+            liquid_core::value!("shop.example.com")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlPath, URL).unwrap(),
+            liquid_core::value!("/p/123%20x")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlScheme, URL).unwrap(),
+            liquid_core::value!("https")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlFragment, URL).unwrap(),
+            liquid_core::value!("reviews")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlPath, "https://shop.example.com").unwrap(),
+            liquid_core::value!("/")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlHost, "mailto:someone@example.com").unwrap(),
+            liquid_core::value!("")
+        );
+
+        let lax = liquid_core::runtime::RuntimeBuilder::new()
+            .set_render_mode(RenderingMode::Lax)
+            .build();
+        for invalid in ["", "not a url", "/p/123?id=7", "shop.example.com/p/123", "https://"] {
+            assert_eq!(crate::call_filter_with_runtime!(lax, UrlHost, invalid).unwrap(), liquid_core::value!(""));
+            assert_eq!(crate::call_filter_with_runtime!(lax, UrlPath, invalid).unwrap(), liquid_core::value!(""));
+            assert_eq!(crate::call_filter_with_runtime!(lax, UrlScheme, invalid).unwrap(), liquid_core::value!(""));
+            assert_eq!(crate::call_filter_with_runtime!(lax, UrlFragment, invalid).unwrap(), liquid_core::value!(""));
+
+            assert!(liquid_core::call_filter!(UrlHost, invalid).is_err());
+            assert!(liquid_core::call_filter!(UrlPath, invalid).is_err());
+            assert!(liquid_core::call_filter!(UrlScheme, invalid).is_err());
+            assert!(liquid_core::call_filter!(UrlFragment, invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn unit_url_query_param() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlQueryParam, URL, "utm_source").unwrap(),
+            liquid_core::value!("spring sale")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlQueryParam, URL, "tag").unwrap(),
+            liquid_core::value!("a")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlQueryParam, URL, "missing").unwrap(),
+            liquid_core::value!("")
+        );
+
+        let lax = liquid_core::runtime::RuntimeBuilder::new()
+            .set_render_mode(RenderingMode::Lax)
+            .build();
+        assert_eq!(
+            crate::call_filter_with_runtime!(lax, UrlQueryParam, "not a url", "id").unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(liquid_core::call_filter!(UrlQueryParam, "not a url", "id").is_err());
+    }
+
+    #[test]
+    fn unit_url_query_params() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlQueryParams, URL).unwrap(),
+            liquid_core::value!({"utm_source": "spring sale", "id": "7", "tag": ["a", "b"]})
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlQueryParams, "https://shop.example.com/").unwrap(),
+            liquid_core::value!({})
+        );
+
+        let lax = liquid_core::runtime::RuntimeBuilder::new()
+            .set_render_mode(RenderingMode::Lax)
+            .build();
+        assert_eq!(
+            crate::call_filter_with_runtime!(lax, UrlQueryParams, "not a url").unwrap(),
+            liquid_core::value!({})
+        );
+        assert!(liquid_core::call_filter!(UrlQueryParams, "not a url").is_err());
+    }
+
+    #[test]
+    fn unit_url_set_param() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "https://shop.example.com/p/123?utm_source=web#reviews", "utm_source", "spring sale").unwrap(),
+            liquid_core::value!("https://shop.example.com/p/123?utm_source=spring+sale#reviews")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "https://shop.example.com/p/123", "utm_source", "email").unwrap(),
+            liquid_core::value!("https://shop.example.com/p/123?utm_source=email")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "https://shop.example.com/?a=1&tag=x&b=2&tag=y", "tag", "a&b=c").unwrap(),
+            liquid_core::value!("https://shop.example.com/?a=1&tag=a%26b%3Dc&b=2")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "/p/123?", "id", 7).unwrap(),
+            liquid_core::value!("/p/123?id=7")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "/p?tag%5B%5D=x&id=1", "tag", liquid_core::value!(["a", "b"])).unwrap(),
+            liquid_core::value!("/p?tag%5B%5D=a&tag%5B%5D=b&id=1")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlSetParam, "/p?id=1", "tag", liquid_core::value!(["b", "a"])).unwrap(),
+            liquid_core::value!("/p?id=1&tag%5B%5D=b&tag%5B%5D=a")
+        );
+    }
+
+    #[test]
+    fn unit_url_remove_param() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlRemoveParam, "https://shop.example.com/p/123?token=abc&id=7", "token").unwrap(),
+            liquid_core::value!("https://shop.example.com/p/123?id=7")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlRemoveParam, "https://shop.example.com/?token=abc#top", "token").unwrap(),
+            liquid_core::value!("https://shop.example.com/#top")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlRemoveParam, "/p?user%5Bname%5D=Jo&user[id]=1&username=x", "user").unwrap(),
+            liquid_core::value!("/p?username=x")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlRemoveParam, "/p", "user").unwrap(),
+            liquid_core::value!("/p")
+        );
+    }
+
+    #[test]
+    fn unit_url_append_path() {
+        assert_eq!(
+            liquid_core::call_filter!(UrlAppendPath, "https://shop.example.com/collections/?ref=email", "summer sale/shoes").unwrap(),
+            liquid_core::value!("https://shop.example.com/collections/summer%20sale/shoes?ref=email")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlAppendPath, "https://shop.example.com", "/p/a+b/").unwrap(),
+            liquid_core::value!("https://shop.example.com/p/a%2Bb/")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(UrlAppendPath, "https://shop.example.com/faq#returns", "99").unwrap(),
+            liquid_core::value!("https://shop.example.com/faq/99#returns")
+        );
+    }
+
+    // Expected values are the results of rails' Hash#to_query
+    #[test]
+    fn unit_to_query() {
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({"id": 7, "items": ["a b", "c"], "user": {"name": "Jo", "tags": {}}})).unwrap(),
+            liquid_core::value!("id=7&items%5B%5D=a+b&items%5B%5D=c&user%5Bname%5D=Jo")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({"ids": [3, 1, 2], "b": 1})).unwrap(),
+            liquid_core::value!("b=1&ids%5B%5D=3&ids%5B%5D=1&ids%5B%5D=2")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({"items": [{"a": 2, "b": 1}, {"a": 4}]})).unwrap(),
+            liquid_core::value!("items%5B%5D%5Ba%5D=2&items%5B%5D%5Bb%5D=1&items%5B%5D%5Ba%5D=4")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({"b": "&=?", "a": nil, "c": true})).unwrap(),
+            liquid_core::value!("a=&b=%26%3D%3F&c=true")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({"id": 7, "q": "x"}), "cart").unwrap(),
+            liquid_core::value!("cart%5Bid%5D=7&cart%5Bq%5D=x")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::value!({})).unwrap(),
+            liquid_core::value!("")
+        );
+        assert_eq!(
+            liquid_core::call_filter!(ToQuery, liquid_core::Value::Nil).unwrap(),
+            liquid_core::value!("")
+        );
+        assert!(liquid_core::call_filter!(ToQuery, "a=1").is_err());
+    }
+}
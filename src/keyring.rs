@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Named secrets that filters can reference by id instead of taking the
+/// key material as a literal template argument.
+#[derive(Clone, Default)]
+pub struct Keyring {
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self {..Default::default()}
+    }
+
+    pub fn insert(&mut self, key_id: String, key: Vec<u8>) {
+        self.keys.insert(key_id, key);
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&[u8]> {
+        self.keys.get(key_id).map(|x| x.as_slice())
+    }
+}
+
+// Only the key ids are printed, never the key material.
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.keys.keys()).finish()
+    }
+}
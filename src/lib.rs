@@ -2,20 +2,35 @@
 mod macros;
 
 mod filters;
+mod click_tracking;
 mod currency_config;
 mod template;
 mod error;
+mod exchange_rates;
+mod keyring;
+mod link_tagging;
 mod utils;
 
 use std::sync::Arc;
 
+pub use click_tracking::{ClickTracking, TrackedLink};
+pub use currency_config::{load_currencies, merge_currencies, CurrencyDefinition, CurrencyTable};
 pub use error::Error;
 pub use error::Result;
+pub use exchange_rates::ExchangeRates;
+pub use keyring::Keyring;
+pub use link_tagging::LinkTagging;
 
 #[derive(Clone, Debug, Default)]
 pub struct RenderContext {
     pub currency_type: Arc<String>,
+    pub currency_table: Option<Arc<CurrencyTable>>,
+    pub exchange_rates: Option<Arc<ExchangeRates>>,
     pub tokio_rt: Option<Arc<tokio::runtime::Runtime>>,
+    pub keyring: Arc<Keyring>,
+    pub render_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub link_tagging: Option<Arc<LinkTagging>>,
+    pub click_tracking: Option<Arc<ClickTracking>>,
 }
 
 impl RenderContext{
@@ -27,7 +42,41 @@ impl RenderContext{
         self.currency_type = Arc::new(currency_type);
     }
 
+    /// Currency definitions for this render only, e.g. the symbols of a tenant.
+    /// They win over the currencies loaded with load_currencies and the compiled ones.
+    pub fn set_currency_table(&mut self, currency_table: CurrencyTable) {
+        self.currency_table = Some(Arc::new(currency_table));
+    }
+
+    /// Rates used by convert_currency and the convert_from option of money.
+    pub fn set_exchange_rates(&mut self, exchange_rates: Arc<ExchangeRates>) {
+        self.exchange_rates = Some(exchange_rates);
+    }
+
     pub fn set_tokio_runtime(&mut self, tokio_rt: Arc<tokio::runtime::Runtime>) {
         self.tokio_rt = Some(tokio_rt);
     }
+
+    pub fn set_keyring(&mut self, keyring: Keyring) {
+        self.keyring = Arc::new(keyring);
+    }
+
+    pub fn add_key(&mut self, key_id: String, key: Vec<u8>) {
+        Arc::make_mut(&mut self.keyring).insert(key_id, key);
+    }
+
+    /// Pins the clock used by time dependent filters, so a render can be reproduced.
+    pub fn set_render_time(&mut self, render_time: chrono::DateTime<chrono::Utc>) {
+        self.render_time = Some(render_time);
+    }
+
+    /// Tags the links of the rendered html once rendering is done.
+    pub fn set_link_tagging(&mut self, link_tagging: LinkTagging) {
+        self.link_tagging = Some(Arc::new(link_tagging));
+    }
+
+    /// Rewrites the links of the rendered html for click tracking, after any link tagging.
+    pub fn set_click_tracking(&mut self, click_tracking: ClickTracking) {
+        self.click_tracking = Some(Arc::new(click_tracking));
+    }
 }
@@ -0,0 +1,148 @@
+use lol_html::{element, rewrite_str, RewriteStrSettings};
+use url::Url;
+
+use crate::error::Error;
+use crate::utils::html_attribute_decode;
+
+/// Query parameters, typically utm_*, added to every link in the rendered html
+/// that points to one of the allowed domains.
+/// Parameters a link already has are left as they are.
+/// mailto:, tel: and other non http links, relative links and links with a data-no-track
+/// attribute are not touched.
+#[derive(Clone, Debug, Default)]
+pub struct LinkTagging {
+    params: Vec<(String, String)>,
+    domains: Vec<String>,
+}
+
+impl LinkTagging {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_param(&mut self, name: String, value: String) {
+        self.params.push((name, value));
+    }
+
+    /// Subdomains of an allowed domain are allowed too.
+    pub fn allow_domain(&mut self, domain: String) {
+        self.domains.push(domain.trim_start_matches('.').to_lowercase());
+    }
+
+    fn is_allowed(&self, url: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return false,
+        };
+        self.domains.iter().any(|domain| {
+            host == domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|x| x.ends_with('.'))
+        })
+    }
+
+    // Returns None when the link is left as is.
+    pub(crate) fn tag(&self, href: &str) -> Option<String> {
+        let mut url = Url::parse(href.trim()).ok()?;
+        if !matches!(url.scheme(), "http" | "https") || !self.is_allowed(&url) {
+            return None;
+        }
+
+        let missing: Vec<&(String, String)> = self
+            .params
+            .iter()
+            .filter(|(name, _)| !url.query_pairs().any(|(existing, _)| existing == name.as_str()))
+            .collect();
+        if missing.is_empty() {
+            return None;
+        }
+
+        let mut query = url.query_pairs_mut();
+        for (name, value) in missing {
+            query.append_pair(name, value);
+        }
+        drop(query);
+        Some(url.into())
+    }
+
+    pub(crate) fn rewrite(&self, html: &str) -> Result<String, Error> {
+        rewrite_str(
+            html,
+            RewriteStrSettings {
+                element_content_handlers: vec![element!("a[href]", |el| {
+                    if el.has_attribute("data-no-track") {
+                        return Ok(());
+                    }
+                    let href = el.get_attribute("href").unwrap_or_default();
+                    let href = html_attribute_decode(&href);
+                    if let Some(tagged) = self.tag(&href) {
+                        el.set_attribute("href", &tagged.replace('&', "&amp;"))?;
+                    }
+                    Ok(())
+                })],
+                ..RewriteStrSettings::default()
+            },
+        )
+        .map_err(|err| Error::RenderingError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagging() -> LinkTagging {
+        let mut tagging = LinkTagging::new();
+        tagging.add_param("utm_source".to_owned(), "email".to_owned());
+        tagging.add_param("utm_campaign".to_owned(), "spring sale".to_owned());
+        tagging.allow_domain("shop.example.com".to_owned());
+        tagging.allow_domain("example.org".to_owned());
+        tagging
+    }
+
+    #[test]
+    fn unit_tag() {
+        let tagging = tagging();
+
+        assert_eq!(
+            tagging.tag("https://shop.example.com/p/1").as_deref(),
+            Some("https://shop.example.com/p/1?utm_source=email&utm_campaign=spring+sale")
+        );
+        assert_eq!(
+            tagging.tag("https://shop.example.com/p/1?id=7&utm_source=sms#top").as_deref(),
+            Some("https://shop.example.com/p/1?id=7&utm_source=sms&utm_campaign=spring+sale#top")
+        );
+        assert_eq!(
+            tagging.tag("http://www.example.org").as_deref(),
+            Some("http://www.example.org/?utm_source=email&utm_campaign=spring+sale")
+        );
+        assert_eq!(tagging.tag("https://shop.example.com/?utm_source=a&utm_campaign=b"), None);
+        assert_eq!(tagging.tag("https://notexample.org/"), None);
+        assert_eq!(tagging.tag("https://example.com/"), None);
+        assert_eq!(tagging.tag("mailto:help@shop.example.com"), None);
+        assert_eq!(tagging.tag("tel:+14155552671"), None);
+        assert_eq!(tagging.tag("/p/1"), None);
+        assert_eq!(tagging.tag("{{ not rendered }}"), None);
+    }
+
+    #[test]
+    fn unit_rewrite() {
+        let html = r#"<p>Hi <a href="https://shop.example.com/p/1?a=1&amp;b=2" class="btn">Shop</a>
+<a href="mailto:help@shop.example.com">Mail</a> <a href='https://shop.example.com/unsubscribe' data-no-track>Unsubscribe</a>
+<a href="https://other.example.net/">Other</a> <a name="top">Top</a></p>"#;
+
+        assert_eq!(
+            tagging().rewrite(html).unwrap(),
+            r#"<p>Hi <a href="https://shop.example.com/p/1?a=1&amp;b=2&amp;utm_source=email&amp;utm_campaign=spring+sale" class="btn">Shop</a>
+<a href="mailto:help@shop.example.com">Mail</a> <a href='https://shop.example.com/unsubscribe' data-no-track>Unsubscribe</a>
+<a href="https://other.example.net/">Other</a> <a name="top">Top</a></p>"#
+        );
+        assert_eq!(LinkTagging::new().rewrite(html).unwrap(), html);
+
+        assert_eq!(
+            tagging().rewrite(r#"<a href="https://shop.example.com/p/1?a=1&#38;b=2&#x26;c=&lt;3&copy=4">Shop</a>"#).unwrap(),
+            r#"<a href="https://shop.example.com/p/1?a=1&amp;b=2&amp;c=%3C3&amp;copy=4&amp;utm_source=email&amp;utm_campaign=spring+sale">Shop</a>"#
+        );
+    }
+}
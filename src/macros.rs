@@ -28,3 +28,17 @@ macro_rules! render_template {
         rendered
     }};
 }
+
+#[allow(unused_macros)]
+#[macro_export]
+macro_rules! call_filter_with_keywords {
+    ($runtime: expr, $filter:expr, $input:expr, [$($args:expr),*], {$($key:literal => $value:expr),*}) => {{
+        let positional = Box::new(vec![$(liquid_core::Expression::Literal(liquid_core::value!($args))),*].into_iter());
+        let keyword = Box::new(vec![$(($key, liquid_core::Expression::Literal(liquid_core::value!($value)))),*].into_iter());
+        let args = liquid_core::parser::FilterArguments { positional, keyword };
+
+        let input = liquid_core::value!($input);
+        liquid_core::ParseFilter::parse(&$filter, args)
+            .and_then(|filter| liquid_core::Filter::evaluate(&*filter, &input, &$runtime))
+    }};
+}
@@ -1,6 +1,9 @@
 
+use std::any::Any;
+
 use liquid_core::Renderable;
 use crate::error::Error;
+use crate::TrackedLink;
 use liquid_core::Runtime;
 
 pub struct Template {
@@ -17,25 +20,67 @@ impl Template {
             .filter(crate::filters::aes::Aes256DecryptV1Deprecated)
             .filter(crate::filters::hashing::Sha1)
             .filter(crate::filters::hashing::Sha256)
+            .filter(crate::filters::hashing::Sha384)
+            .filter(crate::filters::hashing::Sha512)
+            .filter(crate::filters::hashing::Sha3_256)
             .filter(crate::filters::hashing::Md5)
+            .filter(crate::filters::hashing::Crc32)
+            .filter(crate::filters::hashing::HmacSha1)
+            .filter(crate::filters::hashing::HmacSha256)
+            .filter(crate::filters::hashing::HmacSha512)
+            .filter(crate::filters::jwt::JwtEncode)
+            .filter(crate::filters::audience::EmailNormalize)
+            .filter(crate::filters::audience::HashedEmail)
+            .filter(crate::filters::audience::PhoneNormalize)
+            .filter(crate::filters::audience::HashedPhone)
             .filter(crate::filters::array::Shuffle)
             .filter(crate::filters::string::Camelcase)
             .filter(crate::filters::string::AnyContains)
             .filter(crate::filters::string::EscapeNewline)
             .filter(crate::filters::url_encode::UrlEncode)
+            .filter(crate::filters::url_encode::UrlDecode)
             .filter(crate::filters::url_encode::EscapeUrl)
+            .filter(crate::filters::url_filters::UrlHost)
+            .filter(crate::filters::url_filters::UrlPath)
+            .filter(crate::filters::url_filters::UrlScheme)
+            .filter(crate::filters::url_filters::UrlFragment)
+            .filter(crate::filters::url_filters::UrlQueryParam)
+            .filter(crate::filters::url_filters::UrlQueryParams)
+            .filter(crate::filters::url_filters::UrlSetParam)
+            .filter(crate::filters::url_filters::UrlRemoveParam)
+            .filter(crate::filters::url_filters::UrlAppendPath)
+            .filter(crate::filters::url_filters::ToQuery)
             .filter(crate::filters::base64_filters::Base64Encode)
             .filter(crate::filters::base64_filters::Base64StrictEncode)
             .filter(crate::filters::base64_filters::B64Enc)
+            .filter(crate::filters::base64_filters::Base64UrlEncode)
             .filter(crate::filters::base64_filters::Base64Decode)
             .filter(crate::filters::base64_filters::Base64StrictDecode)
             .filter(crate::filters::base64_filters::B64dec)
+            .filter(crate::filters::base64_filters::Base64UrlDecode)
+            .filter(crate::filters::encoding_filters::HexEncode)
+            .filter(crate::filters::encoding_filters::HexDecode)
+            .filter(crate::filters::encoding_filters::Base32Encode)
+            .filter(crate::filters::encoding_filters::Base32Decode)
+            .filter(crate::filters::encoding_filters::Base58Encode)
+            .filter(crate::filters::encoding_filters::Base58Decode)
+            .filter(crate::filters::compression::GzipBase64)
+            .filter(crate::filters::compression::GunzipBase64)
+            .filter(crate::filters::compression::DeflateBase64Url)
+            .filter(crate::filters::compression::InflateBase64Url)
             .filter(crate::filters::money::Money)
             .filter(crate::filters::money::MoneyWithoutTrailingZeros)
+            .filter(crate::filters::currency::ConvertCurrency)
+            .filter(crate::filters::currency::CurrencySymbol)
+            .filter(crate::filters::currency::CurrencyName)
+            .filter(crate::filters::currency::CurrencyPrecision)
+            .filter(crate::filters::currency::CurrencySymbolFirst)
             .filter(crate::filters::number::NumberWithDelimiter)
             .filter(crate::filters::number::NumberToPercentage)
             .filter(crate::filters::number::NumberWithPrecision)
             .filter(crate::filters::number::NumberToCurrency)
+            .filter(crate::filters::number::NumberToHuman)
+            .filter(crate::filters::number::NumberToHumanSize)
             .filter(crate::filters::number::NumberBetween)
             .filter(crate::filters::number::NumberMoreThan)
             .filter(crate::filters::number::NumberLessThan)
@@ -63,6 +108,15 @@ impl Template {
         rc: T,
         globals: &liquid::model::Object,
     ) -> Result<String, Error>  {
+        self.render_with_manifest(rc, globals).map(|rendered| rendered.output)
+    }
+
+    /// Same as render_with_context, also returning the links rewritten for click tracking.
+    pub fn render_with_manifest<T: Default + 'static>(
+        &self,
+        rc: T,
+        globals: &liquid::model::Object,
+    ) -> Result<Rendered, Error>  {
         const BEST_GUESS: usize = 10_000;
         let mut buffer = Vec::with_capacity(BEST_GUESS);
         let runtime = liquid_core::runtime::RuntimeBuilder::new()
@@ -74,22 +128,129 @@ impl Template {
         };
         let runtime = runtime.build();
 
+        // Post render passes are configured on the RenderContext
+        let render_context = (&rc as &dyn Any).downcast_ref::<crate::RenderContext>();
+        let link_tagging = render_context.and_then(|x| x.link_tagging.clone());
+        let click_tracking = render_context.and_then(|x| x.click_tracking.clone());
+
         {
         let mut cxt = runtime.registers().get_mut::<T>();
         *cxt = rc;
         }
 
-        match self.compiled.template.render_to(&mut buffer, &runtime) {
-            Ok(()) => Ok(convert_buffer(buffer)),
-            Err(err) => {
-                Err(Error::RenderingError(
-                    err.to_string(),
-                ))
-            }
+        if let Err(err) = self.compiled.template.render_to(&mut buffer, &runtime) {
+            return Err(Error::RenderingError(
+                err.to_string(),
+            ));
+        }
+
+        let mut output = convert_buffer(buffer);
+        if let Some(link_tagging) = link_tagging {
+            output = link_tagging.rewrite(&output)?;
+        }
+        let mut links = Vec::new();
+        if let Some(click_tracking) = click_tracking {
+            (output, links) = click_tracking.rewrite(&output)?;
         }
+        Ok(Rendered { output, links })
     }
 }
 
+pub struct Rendered {
+    pub output: String,
+    /// Links rewritten for click tracking, in document order.
+    pub links: Vec<TrackedLink>,
+}
+
 fn convert_buffer(buffer: Vec<u8>) -> String {
     unsafe { String::from_utf8_unchecked(buffer) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClickTracking, LinkTagging};
+
+    const TEMPLATE: &str = r#"<p>Hi {{ name }}, <a href="{{ url }}">Shop</a></p>"#;
+    const TRACKING_URL: &str = "https://track.example/c/";
+
+    fn globals() -> liquid::model::Object {
+        liquid::object!({"name": "Jo", "url": "https://shop.example.com/p/1"})
+    }
+
+    #[test]
+    fn unit_render_link_tagging() {
+        let template = Template::parse(TEMPLATE.to_owned()).unwrap();
+
+        let mut tagging = LinkTagging::new();
+        tagging.add_param("utm_source".to_owned(), "email".to_owned());
+        tagging.allow_domain("example.com".to_owned());
+        let mut rc = crate::RenderContext::new();
+        rc.set_link_tagging(tagging);
+
+        assert_eq!(
+            template.render_with_context(rc, &globals()).unwrap(),
+            r#"<p>Hi Jo, <a href="https://shop.example.com/p/1?utm_source=email">Shop</a></p>"#
+        );
+        assert_eq!(
+            template.render_with_context(crate::RenderContext::new(), &globals()).unwrap(),
+            r#"<p>Hi Jo, <a href="https://shop.example.com/p/1">Shop</a></p>"#
+        );
+    }
+
+    fn tracked_link() -> TrackedLink {
+        TrackedLink {
+            url: "https://shop.example.com/p/1?a=1&b=2".to_owned(),
+            index: 0,
+            recipient_id: "user-42".to_owned(),
+        }
+    }
+
+    #[test]
+    fn unit_render_click_tracking_signed() {
+        let template = Template::parse(TEMPLATE.to_owned()).unwrap();
+        let globals = liquid::object!({"name": "Jo", "url": "https://shop.example.com/p/1?a=1&b=2"});
+
+        let mut tracking = ClickTracking::signed(TRACKING_URL.to_owned(), b"secret".to_vec());
+        tracking.set_recipient_id("user-42".to_owned());
+        let mut rc = crate::RenderContext::new();
+        rc.set_click_tracking(tracking);
+
+        let rendered = template.render_with_manifest(rc, &globals).unwrap();
+        assert_eq!(
+            rendered.output,
+            r#"<p>Hi Jo, <a href="https://track.example/c/eyJ1IjoiaHR0cHM6Ly9zaG9wLmV4YW1wbGUuY29tL3AvMT9hPTEmYj0yIiwiaSI6MCwiciI6InVzZXItNDIifQ.JoZ4KqyQw5cCaviiKOLYuEOMMRp5nJS4j0gs52F5rg8">Shop</a></p>"#
+        );
+        assert_eq!(rendered.links, vec![tracked_link()]);
+
+        // Only a RenderContext configures click tracking
+        let rendered = template.render_with_manifest((), &globals).unwrap();
+        assert_eq!(
+            rendered.output,
+            r#"<p>Hi Jo, <a href="https://shop.example.com/p/1?a=1&b=2">Shop</a></p>"#
+        );
+        assert!(rendered.links.is_empty());
+    }
+
+    #[test]
+    fn unit_render_click_tracking_encrypted() {
+        let template = Template::parse(TEMPLATE.to_owned()).unwrap();
+        let globals = liquid::object!({"name": "Jo", "url": "https://shop.example.com/p/1?a=1&b=2"});
+
+        let mut tracking = ClickTracking::encrypted(TRACKING_URL.to_owned(), [7u8; 32].to_vec()).unwrap();
+        tracking.set_recipient_id("user-42".to_owned());
+        let mut rc = crate::RenderContext::new();
+        rc.set_click_tracking(tracking.clone());
+
+        let rendered = template.render_with_manifest(rc, &globals).unwrap();
+        let payload = rendered
+            .output
+            .strip_prefix(r#"<p>Hi Jo, <a href="https://track.example/c/"#)
+            .and_then(|x| x.strip_suffix(r#"">Shop</a></p>"#))
+            .unwrap();
+        // Signed payloads are json.signature, encrypted ones a single base64url part
+        assert!(!payload.contains('.'));
+        assert_eq!(tracking.verify(payload).unwrap(), tracked_link());
+        assert_eq!(rendered.links, vec![tracked_link()]);
+    }
+}
@@ -1,51 +1,149 @@
 use liquid_core::Result;
-use bigdecimal::num_traits::Pow;
-use bigdecimal::BigDecimal;
-use bigdecimal::FromPrimitive;
+use bigdecimal::num_bigint::Sign;
+use bigdecimal::{BigDecimal, RoundingMode};
 use liquid_core::ValueView;
 use liquid_core::model::KStringCow;
 use std::borrow::Cow;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use crate::filters::filter_error;
+use crate::filters::invalid_argument;
 use crate::filters::invalid_input;
 use crate::RenderContext;
 
 
+/// How numbers are rounded to their last kept digit. Modes are named after Ruby's BigDecimal ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Rounding {
+    /// 2.5 → 3, -2.5 → -3. Ruby and Rails default.
+    #[default]
+    HalfUp,
+    /// 2.5 → 2, 3.5 → 4, also known as banker's rounding.
+    HalfEven,
+    /// 2.5 → 2, -2.5 → -2
+    HalfDown,
+    /// Towards zero
+    Truncate,
+    /// Away from zero
+    Up,
+    Ceiling,
+    Floor,
+}
+
+impl Rounding {
+    pub(crate) fn parse(mode: Option<&str>) -> Result<Self> {
+        match mode.unwrap_or("half_up") {
+            "half_up" | "default" => Ok(Rounding::HalfUp),
+            "half_even" | "banker" => Ok(Rounding::HalfEven),
+            "half_down" => Ok(Rounding::HalfDown),
+            "truncate" | "down" => Ok(Rounding::Truncate),
+            "up" => Ok(Rounding::Up),
+            "ceiling" | "ceil" => Ok(Rounding::Ceiling),
+            "floor" => Ok(Rounding::Floor),
+            other => Err(invalid_argument("round_mode".to_owned(), format!("Unknown rounding mode {}, expected half_up, half_even, half_down, truncate, up, ceiling or floor", other))),
+        }
+    }
+
+    fn mode(self) -> RoundingMode {
+        match self {
+            Rounding::HalfUp => RoundingMode::HalfUp,
+            Rounding::HalfEven => RoundingMode::HalfEven,
+            Rounding::HalfDown => RoundingMode::HalfDown,
+            Rounding::Truncate => RoundingMode::Down,
+            Rounding::Up => RoundingMode::Up,
+            Rounding::Ceiling => RoundingMode::Ceiling,
+            Rounding::Floor => RoundingMode::Floor,
+        }
+    }
+}
+
+// Rounding 1e999999999 or to a precision of 999999999 would build a billion digits number.
+const MAX_EXPONENT: i64 = 400;
+const MAX_PRECISION: i64 = 100;
+
+/// Parses a number as is, without going through f64.
+/// Numbers with an exponent beyond MAX_EXPONENT are not numbers either.
+pub(crate) fn parse_decimal(number: &str) -> Result<BigDecimal> {
+    let number = BigDecimal::from_str(number.trim()).map_err(|_x| {
+        invalid_input("Number expected")
+    })?;
+    if number.fractional_digit_count().abs() > MAX_EXPONENT {
+        return Err(invalid_input("Number expected"));
+    }
+    Ok(number)
+}
+
+pub(crate) fn check_precision(precision: i64) -> Result<i64> {
+    if precision.abs() > MAX_PRECISION {
+        return Err(invalid_argument("precision".to_owned(), format!("Precision {} out of range, expected at most {}", precision, MAX_PRECISION)));
+    }
+    Ok(precision)
+}
+
+/// Rounds to precision digits after the fractional separator.
+pub(crate) fn round_decimal(number: &BigDecimal, precision: i64, rounding: Rounding) -> BigDecimal {
+    number.with_scale_round(precision, rounding.mode())
+}
+
+/// Rounds to digits significant digits.
+pub(crate) fn round_significant(number: &BigDecimal, digits: u64, rounding: Rounding) -> BigDecimal {
+    match NonZeroU64::new(digits) {
+        Some(digits) => number.with_precision_round(digits, rounding.mode()),
+        None => number.clone(),
+    }
+}
+
+// Digits before the fractional separator, 0.001 having -2 of them, as Rails' digit_count.
+pub(crate) fn digit_count(number: &BigDecimal) -> i64 {
+    if number.sign() == Sign::NoSign {
+        return 1;
+    }
+    number.digits() as i64 - number.fractional_digit_count()
+}
+
+/// Plain notation with exactly precision fractional digits. Zero has no sign.
+pub(crate) fn decimal_to_string(number: &BigDecimal, precision: i64) -> String {
+    let precision = precision.max(0);
+    let (int, _) = number.with_scale(precision).as_bigint_and_exponent();
+    let sign = if int.sign() == Sign::Minus { "-" } else { "" };
+    let digits = int.magnitude().to_string();
+    if precision == 0 {
+        return format!("{}{}", sign, digits);
+    }
+
+    let digits = format!("{:0>width$}", digits, width = precision as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - precision as usize);
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn number_with_precision(number_str: &str,
-    thousands_delimiter: Option<char>, // Sets the thousands delimiter (defaults to "").
-    fractional_separator: Option<char>, // Sets the separator between the fractional and integer digits (defaults to ".").
+    thousands_delimiter: Option<&str>, // Sets the thousands delimiter (defaults to ",").
+    fractional_separator: Option<&str>, // Sets the separator between the fractional and integer digits (defaults to ".").
     precision: Option<i64>,
-    significant: Option<u64>,
+    significant: Option<bool>, // Precision counts significant digits rather than fractional digits (defaults to false).
     strip_insignificant_zeros: Option<bool>,
-    _format: Option<&str>,
+    format: Option<&str>, // Sets the format of the output string, %n being the number (defaults to "%n").
+    grouping: &[usize],
+    rounding: Rounding,
 ) -> Result<String>
 {
-    let number = number_str.parse::<f64>().map_err(|_x| {
-        invalid_input("Number expected")
-    })?;
+    let number = parse_decimal(number_str)?;
 
-    let separator = fractional_separator.unwrap_or('.');
-    let delimiter = thousands_delimiter.unwrap_or(',');
-    let mut precision = precision.unwrap_or(3) as i32;
-    let rounded_number: BigDecimal;
-    if significant.is_some() && precision > 0 {
-        let digits = if number == 0.0 {
-            rounded_number = BigDecimal::default();
-            1
-        } else {
-            let digits = (number.log10() + 1.0).floor() as i32;
-            let prec_10 = 10.0.pow(digits - precision);
-            let div: f64 = number/prec_10;
-            let rounded = div.round() * prec_10;
-            rounded_number = bigdecimal::BigDecimal::from_f64(rounded).ok_or_else(|| invalid_input("Number expected"))?;
-            digits
-        };
-        precision = (precision - digits).abs();
+    let separator = fractional_separator.unwrap_or(".");
+    let delimiter = thousands_delimiter.unwrap_or(",");
+    let mut precision = check_precision(precision.unwrap_or(3))?;
+    // A precision of 0 significant digits would always give 0, Rails ignores significant then
+    let rounded_number = if significant.unwrap_or(false) && precision > 0 {
+        // precision counts significant digits, which rounding may add one to: 99.9 → 100
+        let rounded_number = round_significant(&number, precision as u64, rounding);
+        precision = (precision - digit_count(&rounded_number)).max(0);
+        rounded_number
     } else {
-        let prec_10 = (10.pow(precision as u32)) as f64;
-        let b = BigDecimal::from_f64(number * prec_10).ok_or_else(|| invalid_input("Number expected"))?;
-        rounded_number = b.round(0)/ prec_10;
-    }
+        round_decimal(&number, precision, rounding)
+    };
 
-    let formatted = format!("{:01.prec$}", rounded_number, prec = precision as usize);
+    let formatted = decimal_to_string(&rounded_number, precision);
     // Strip zeros before running thru number formatter. that way a . separates the decimal part
     let formatted = if strip_insignificant_zeros.unwrap_or(false) {
         fn_strip_insignificant_zeros(&formatted)
@@ -53,12 +151,13 @@ pub(crate) fn number_with_precision(number_str: &str,
         Cow::Owned(formatted)
     };
 
-    let formatted_number = number_format(&formatted,
+    let formatted_number = number_format_grouped(&formatted,
         delimiter,
-        separator
+        separator,
+        grouping,
     );
 
-    Ok(formatted_number.to_string())
+    Ok(format.unwrap_or("%n").replace("%n", &formatted_number))
 }
 
 
@@ -75,42 +174,64 @@ pub(crate) fn fn_strip_insignificant_zeros(num: &str) -> Cow<'_, str> {
     Cow::Owned(format!("{}.{}", first_part, trimmed))
 }
 
+/// Digit group sizes, from the fractional separator leftwards. The last size repeats,
+/// so [3] gives 1,000,000, [3, 2] the Indian 10,00,000 and [4] 100,0000.
+pub(crate) const THOUSANDS: &[usize] = &[3];
+
+/// Parses group sizes written as "3", "3,2" or "4".
+pub(crate) fn parse_grouping(grouping: &str) -> Result<Vec<usize>> {
+    let invalid = || invalid_argument("grouping".to_owned(), format!("Invalid grouping {}, expected group sizes like 3 or 3,2", grouping));
+    let sizes = grouping
+        .split(',')
+        .map(|x| x.trim().parse::<usize>().ok().filter(|x| *x > 0))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or_else(invalid)?;
+    Ok(sizes)
+}
+
+#[cfg(test)]
 pub(crate) fn number_format(number: &str,
     thousands_delimiter: char, // Sets the thousands delimiter (defaults to "").
     fractional_separator: char, // Sets the separator between the fractional and integer digits (defaults to ".").
 ) -> String
+{
+    number_format_grouped(number,
+        &thousands_delimiter.to_string(),
+        &fractional_separator.to_string(),
+        THOUSANDS,
+    )
+}
+
+pub(crate) fn number_format_grouped(number: &str,
+    thousands_delimiter: &str,
+    fractional_separator: &str,
+    grouping: &[usize],
+) -> String
 {
     let parts: Vec<&str> = number.split('.').collect();
 
-    let mut chars = parts[0].chars();
-    let is_neg = if parts[0].starts_with('-') {
-        chars.next();
-        true
-    } else {
-        false
+    let (sign, integer) = match parts[0].strip_prefix('-') {
+        Some(integer) => ("-", integer),
+        None => ("", parts[0]),
     };
-    let mut final_vec = vec![];
-    for (pos, char) in chars.rev().enumerate() {
-        if pos > 0 && pos % 3 == 0 {
-            final_vec.push(thousands_delimiter);
-            final_vec.push(char);
-        } else {
-            final_vec.push(char);
-        }
-    }
-    if is_neg {
-        final_vec.push('-');
+    let mut digits: Vec<char> = integer.chars().collect();
+    let mut groups = vec![];
+    let mut sizes = grouping.iter();
+    let mut size = sizes.next().copied().unwrap_or(0);
+    while size > 0 && digits.len() > size {
+        groups.push(String::from_iter(digits.split_off(digits.len() - size)));
+        size = sizes.next().copied().unwrap_or(size);
     }
-    final_vec.reverse();
+    groups.push(String::from_iter(digits));
+    groups.reverse();
+
+    let mut formatted = format!("{}{}", sign, groups.join(thousands_delimiter));
     // Decimal part
     if parts.len() > 1 {
-        final_vec.push(fractional_separator);
-        for char in parts[1].chars() {
-            final_vec.push(char);
-        }
+        formatted.push_str(fractional_separator);
+        formatted.push_str(parts[1]);
     }
-
-    String::from_iter(final_vec)
+    formatted
 }
 
 
@@ -122,40 +243,163 @@ pub(crate) fn default_currency_type(render_context: &Option<RenderContext>) -> S
     }
 }
 
-pub(crate) fn currency_format(currency: &str) -> currency_rs::CurrencyOpts {
-    let currency = currency.to_ascii_uppercase();
-    let currency_hash = crate::currency_config::currency_config(&currency);
+/// Decodes the character references of a raw html attribute value, &amp; &#38; &#x26; and named ones,
+/// following the html rules for attributes: ?a=1&copy=2 keeps its &copy.
+pub(crate) fn html_attribute_decode(value: &str) -> Cow<'_, str> {
+    htmlize::unescape_attribute(value)
+}
+
+pub(crate) fn render_time(render_context: &Option<RenderContext>) -> chrono::DateTime<chrono::Utc> {
+    render_context
+        .as_ref()
+        .and_then(|cxt| cxt.render_time)
+        .unwrap_or_else(chrono::Utc::now)
+}
 
-    if let Some(config) = currency_hash {
-        let number_format = if config.is_symbol_prefix {
-            "! #"
-        } else {
-            "# !"
+pub(crate) fn keyring_key(render_context: &Option<RenderContext>, key_id: &str) -> Result<Vec<u8>> {
+    render_context
+        .as_ref()
+        .and_then(|cxt| cxt.keyring.get(key_id))
+        .map(|key| key.to_vec())
+        .ok_or_else(|| invalid_argument("key_id".to_owned(), format!("Unknown key id {}", key_id)))
+}
+
+/// How amounts of a currency are written, with the defaults of unknown currencies filled in.
+pub(crate) struct CurrencyFormat {
+    pub symbol: String,
+    pub is_symbol_prefix: bool,
+    pub separator: String,
+    pub delimiter: String,
+    pub grouping: Vec<usize>,
+    pub precision: i64,
+}
+
+pub(crate) fn currency_format(render_context: &Option<RenderContext>, currency: &str) -> CurrencyFormat {
+    let overrides = render_context.as_ref().and_then(|cxt| cxt.currency_table.as_deref());
+    let config = crate::currency_config::resolve_currency(currency, overrides).unwrap_or_default();
+
+    let or_default = |field: Option<String>, default: &str| -> String {
+        field.filter(|x| !x.is_empty()).unwrap_or_else(|| default.to_owned())
+    };
+    CurrencyFormat {
+        symbol: or_default(config.symbol, "$"),
+        is_symbol_prefix: config.is_symbol_prefix.unwrap_or(false),
+        separator: or_default(config.separator, ","),
+        delimiter: or_default(config.delimiter, "."),
+        grouping: config.grouping.filter(|x| !x.is_empty()).unwrap_or_else(|| THOUSANDS.to_vec()),
+        // Minor units of the currency, 2 when it has none, like gold.
+        precision: config.precision.unwrap_or(2),
+    }
+}
+
+/// Which zeros of the fractional part money leaves out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TrailingZeros {
+    #[default]
+    Keep,
+    /// Whole amounts only: 10.00 → 10, 10.50 stays.
+    StripWhole,
+    /// 10.00 → 10, 10.50 → 10.5
+    StripInsignificant,
+}
+
+/// How the currency filters show the sign of an amount.
+/// Formats use %u for the unit and %n for the number, as in Rails' number_to_currency.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SignFormat {
+    /// Format of negative amounts, "-%u%n", "%u-%n", "(%u%n)" or "%n- %u".
+    /// Defaults to the format with a leading -.
+    pub negative_format: Option<String>,
+    /// Puts a + before positive amounts. Zero has no sign.
+    pub positive_sign: bool,
+}
+
+impl SignFormat {
+    /// number may start with a -, which the negative format replaces.
+    pub(crate) fn apply(&self, format: &str, unit: &str, number: &str) -> Result<String> {
+        let format = match number.strip_prefix('-') {
+            Some(_) => match &self.negative_format {
+                Some(negative_format) if !negative_format.contains("%n") => {
+                    return Err(invalid_argument("negative_format".to_owned(), format!("Invalid format {}, expected a %n for the number", negative_format)));
+                }
+                Some(negative_format) => negative_format.clone(),
+                None => format!("-{}", format),
+            },
+            None if self.positive_sign && number.bytes().any(|x| (b'1'..=b'9').contains(&x)) => format!("+{}", format),
+            None => format.to_owned(),
         };
-        currency_rs::CurrencyOpts::new()
-            .set_symbol(if config.symbol.is_empty() { "$" } else {config.symbol} )
-            .set_separator(if config.separator.is_empty() { "," } else {config.separator})
-            .set_decimal(if config.delimiter.is_empty() { "." } else {config.delimiter })
-            .set_pattern(number_format)
-            .set_precision(if config.precision.is_none() { 2 } else {config.precision.unwrap() })
-    } else {
-        let number_format = "# !";
-        currency_rs::CurrencyOpts::new()
-            .set_symbol("$")
-            .set_separator(",")
-            .set_decimal(".")
-            .set_pattern(number_format)
-            .set_precision(2)
+        let number = number.strip_prefix('-').unwrap_or(number);
+        Ok(format.replace("%u", unit).replace("%n", number).trim().to_owned())
+    }
+}
+
+/// Options of the money filters.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MoneyFormat {
+    pub use_symbol: Option<bool>,
+    pub use_space: Option<bool>,
+    pub rounding: Rounding,
+    pub trailing_zeros: TrailingZeros,
+    /// Currency of the amount, converted to the currency it is shown in.
+    pub convert_from: Option<String>,
+    pub sign: SignFormat,
+    /// Shows the currency code after the number, 10.00 USD, in place of the symbol.
+    pub show_code: bool,
+}
+
+/// Converts with the exchange rates of the render context. The result is exact unless
+/// neither currency is the base of the rates.
+pub(crate) fn convert_currency(render_context: &Option<RenderContext>, amount: &BigDecimal, from: &str, to: &str) -> Result<BigDecimal> {
+    let rates = render_context
+        .as_ref()
+        .and_then(|cxt| cxt.exchange_rates.as_ref())
+        .ok_or_else(|| filter_error("No exchange rates set on the render context"))?;
+    rates
+        .convert(amount, from, to)
+        .ok_or_else(|| filter_error(format!("No exchange rate from {} to {}", from, to)))
+}
+
+// Amounts are numbers, "1234.5" or "1e3", or formatted ones carrying a symbol, spaces and
+// thousands delimiters, "$1,234.50" or "1 234.50 USD", possibly negative in accounting style, "(10.00)".
+// Anything else, like "1.234,50", is not an amount.
+fn parse_amount(amount: &str) -> Option<BigDecimal> {
+    let amount = amount.trim();
+    if let Ok(number) = parse_decimal(amount) {
+        return Some(number);
+    }
+
+    let (parenthesized, amount) = match amount.strip_prefix('(').and_then(|x| x.strip_suffix(')')) {
+        Some(amount) => (true, amount),
+        None => (false, amount),
+    };
+    let is_symbol = |c: char| !(c.is_ascii_digit() || c == '-' || c == '.' || c == ',');
+    // The sign may come before or after the symbol, -$10 or $-10
+    let amount = amount.trim_matches(is_symbol);
+    let (minus, amount) = match amount.strip_prefix('-') {
+        Some(amount) => (true, amount.trim_matches(is_symbol)),
+        None => (false, amount),
+    };
+    let amount: String = amount.chars().filter(|c| !c.is_whitespace()).collect();
+
+    // Thousands delimiters only between groups of 3 digits
+    let (integer, fraction) = amount.split_once('.').unwrap_or((&amount, ""));
+    let groups: Vec<&str> = integer.split(',').collect();
+    let is_digits = |x: &str| !x.is_empty() && x.bytes().all(|c| c.is_ascii_digit());
+    let grouped = groups.len() == 1
+        || (groups[0].len() <= 3 && groups[1..].iter().all(|x| x.len() == 3));
+    if !groups.iter().all(|x| is_digits(x)) || !grouped || !fraction.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
     }
 
+    let number = parse_decimal(&format!("{}.{}", groups.concat(), fraction)).ok()?;
+    Some(if parenthesized != minus { -number } else { number })
 }
 
 pub(crate) fn format_currency(
     render_context: &Option<RenderContext>,
     value: &dyn ValueView,
-    use_symbol: Option<bool>,
-    use_space: Option<bool>,
     currency_type: Option<KStringCow<'_>>,
+    options: &MoneyFormat,
 )  -> Result<String> {
     let value = if let Some(arr) = value.as_array() {
         if let Some(val) = arr.first() {
@@ -171,23 +415,6 @@ pub(crate) fn format_currency(
         return Ok("".to_string());
     }
 
-    let currency = match currency_rs::Currency::new_string(value.as_str(), None) {
-        Ok(v) => v,
-        Err(_) => {
-            return Ok(value.to_string());
-        },
-    };
-    let opt = currency_rs::CurrencyOpts::new()
-        .set_symbol("")
-        .set_separator(",")
-        .set_precision(2)
-        .set_decimal(".");
-
-    if !use_symbol.unwrap_or(true) {
-        let formatted = currency_rs::Currency::new_cur(currency, Some(opt)).format();
-        return Ok(formatted);
-    }
-
     let currency_type = currency_type.map(|x| x.to_string()).unwrap_or_else(|| {
         default_currency_type(render_context)
     });
@@ -196,11 +423,41 @@ pub(crate) fn format_currency(
     } else {
         currency_type
     };
-    let currency_opt = currency_format(&currency_type);
-    let mut formatted_as_money = currency_rs::Currency::new_cur(currency, Some(currency_opt)).format();
-    if !use_space.unwrap_or(true) {
-        formatted_as_money = formatted_as_money.replace(' ', "");
-    }
+    let format = currency_format(render_context, &currency_type);
+
+    let number = match parse_amount(value.as_str()) {
+        Some(number) => number,
+        None => {
+            return Ok(value.to_string());
+        },
+    };
+    let number = match &options.convert_from {
+        Some(from) => convert_currency(render_context, &number, from, &currency_type)?,
+        None => number,
+    };
+    let rounded = round_decimal(&number, format.precision, options.rounding);
+    let amount = decimal_to_string(&rounded, format.precision);
+    // Trimmed while the fractional separator is still a .
+    let amount = match options.trailing_zeros {
+        TrailingZeros::Keep => amount,
+        TrailingZeros::StripWhole => match amount.split_once('.') {
+            Some((integer, fraction)) if fraction.bytes().all(|x| x == b'0') => integer.to_owned(),
+            _ => amount,
+        },
+        TrailingZeros::StripInsignificant => fn_strip_insignificant_zeros(&amount).into_owned(),
+    };
+    let space = if options.use_space.unwrap_or(true) { " " } else { "" };
+    let (format, unit, number) = if options.show_code {
+        let number = number_format_grouped(&amount, &format.separator, &format.delimiter, &format.grouping);
+        ("%n %u".to_owned(), currency_type.to_ascii_uppercase(), number)
+    } else if !options.use_symbol.unwrap_or(true) {
+        ("%n".to_owned(), String::new(), number_format_grouped(&amount, ",", ".", &format.grouping))
+    } else {
+        let number = number_format_grouped(&amount, &format.separator, &format.delimiter, &format.grouping);
+        let layout = if format.is_symbol_prefix { format!("%u{}%n", space) } else { format!("%n{}%u", space) };
+        (layout, format.symbol, number)
+    };
+    let formatted_as_money = options.sign.apply(&format, &unit, &number)?;
 
     Ok(formatted_as_money)
 }